@@ -0,0 +1,259 @@
+use super::core::{Candle, DataSet};
+use super::gaps::{self, GapFill};
+use super::renko::{self, Renko};
+use super::resample::{self, Timeframe};
+
+/// Any bar series a dataset can be rebuilt as. The label is the resample
+/// cache key, so every kind is cached and queried like a timeframe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BarType {
+    Time(Timeframe),
+    Renko(Renko),
+    /// `RANGE:<pips>`: each bar spans a fixed high-low range.
+    Range(f64),
+    /// `TICK:<n>`: each bar holds `n` ticks.
+    Ticks(u64),
+    /// `VOL:<v>`: each bar holds `v` volume.
+    Volume(f64),
+    /// `HA:<bars>` (`HA` alone for the source bars): Heikin-Ashi candles
+    /// of another bar type.
+    HeikinAshi(Option<Box<BarType>>),
+    /// `FILL:<fill>:<timeframe>` (`FILL:<fill>` for the source bars): time
+    /// bars with their gaps filled.
+    Filled(GapFill, Option<Box<BarType>>),
+}
+
+impl BarType {
+    pub fn parse(label: &str) -> Result<BarType, String> {
+        let invalid = || format!("invalid bar type: {}", label);
+        let upper = label.trim().to_ascii_uppercase();
+        if upper.starts_with("RENKO") {
+            return Ok(BarType::Renko(Renko::parse(label)?));
+        }
+        if upper == "HA" {
+            return Ok(BarType::HeikinAshi(None));
+        }
+        if upper.starts_with("HA:") {
+            // zone names in the inner label are case sensitive
+            return match BarType::parse(&label.trim()[3..])? {
+                BarType::HeikinAshi(_) => Err(invalid()),
+                inner => Ok(BarType::HeikinAshi(Some(Box::new(inner)))),
+            };
+        }
+        if upper.starts_with("FILL:") {
+            let (fill, inner) = match label.trim()[5..].split_once(':') {
+                Some((fill, inner)) => (fill, Some(BarType::parse(inner)?)),
+                None => (&label.trim()[5..], None),
+            };
+            return match inner {
+                None | Some(BarType::Time(_)) => {
+                    Ok(BarType::Filled(GapFill::parse(fill)?, inner.map(Box::new)))
+                }
+                Some(_) => Err("gap filling applies to time based bars".to_string()),
+            };
+        }
+        // anything else is a timeframe, whose session offsets contain ':' too
+        let (kind, size) = match upper.split_once(':') {
+            Some((kind @ ("RANGE" | "TICK" | "VOL"), size)) => (kind, size),
+            _ => return Ok(BarType::Time(Timeframe::parse(label)?)),
+        };
+        let size = size.parse::<f64>().ok().filter(|s| s.is_finite() && *s > 0.0);
+        match (kind, size) {
+            ("RANGE", Some(pips)) => Ok(BarType::Range(pips)),
+            ("TICK", Some(n)) if n.fract() == 0.0 => Ok(BarType::Ticks(n as u64)),
+            ("VOL", Some(volume)) => Ok(BarType::Volume(volume)),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            BarType::Time(timeframe) => timeframe.label(),
+            BarType::Renko(renko) => renko.label(),
+            BarType::Range(pips) => format!("RANGE:{}", pips),
+            BarType::Ticks(n) => format!("TICK:{}", n),
+            BarType::Volume(volume) => format!("VOL:{}", volume),
+            BarType::HeikinAshi(None) => "HA".to_string(),
+            BarType::HeikinAshi(Some(inner)) => format!("HA:{}", inner.label()),
+            BarType::Filled(fill, None) => format!("FILL:{}", fill.label()),
+            BarType::Filled(fill, Some(inner)) => format!("FILL:{}:{}", fill.label(), inner.label()),
+        }
+    }
+}
+
+/// Finest source interval `FILL:<mode>` fills without a timeframe.
+const MIN_RAW_FILL_SECS: i64 = 60;
+
+/// Builds `bar_type` bars from `dataset`. Bars that are not time based
+/// should be built from the finest timeframe available.
+pub fn build(dataset: &DataSet, bar_type: &BarType) -> Result<DataSet, String> {
+    let candles = match bar_type {
+        BarType::Time(timeframe) => return resample::resample(dataset, timeframe.clone()),
+        BarType::Renko(options) => return renko::renko(dataset, options),
+        BarType::Range(pips) => {
+            let range = match dataset.candles.first() {
+                Some(first) => pips * renko::pip_size(first.close),
+                None => 0.0,
+            };
+            range_bars(dataset, range)?
+        }
+        // tick exports have one row per tick and no tick volume column
+        BarType::Ticks(n) => accumulate(dataset, |bar, merged| {
+            bar.tick_volume.unwrap_or(merged as f64) >= *n as f64
+        })?,
+        BarType::Volume(volume) => accumulate(dataset, |bar, _| bar.volume >= *volume)?,
+        BarType::HeikinAshi(None) => heikin_ashi(&dataset.candles),
+        BarType::HeikinAshi(Some(inner)) => heikin_ashi(&build(dataset, inner)?.candles),
+        BarType::Filled(fill, inner) => {
+            let (bars, interval) = match inner.as_deref() {
+                Some(BarType::Time(timeframe)) => {
+                    (resample::resample(dataset, timeframe.clone())?, Some(timeframe.interval))
+                }
+                _ => {
+                    let interval = resample::infer_interval(&dataset.candles);
+                    // tick data would get a bar for every quiet second
+                    if interval.and_then(|i| i.seconds()).is_some_and(|secs| secs < MIN_RAW_FILL_SECS) {
+                        return Err(format!(
+                            "source bars are finer than M1; give a timeframe to fill, e.g. FILL:{}:M1",
+                            fill.label()
+                        ));
+                    }
+                    (dataset.clone(), interval)
+                }
+            };
+            match interval {
+                Some(interval) => gaps::fill_gaps(&bars, interval, fill)?.candles,
+                None => bars.candles,
+            }
+        }
+    };
+    Ok(DataSet {
+        source_path: dataset.source_path.clone(),
+        candles,
+        timeframe: Some(bar_type.label()),
+    })
+}
+
+/// Merges source bars until `complete` accepts the bar being built, given
+/// how many source bars it holds. Bars open at the time of their first
+/// source bar; the last one may be partial.
+fn accumulate(dataset: &DataSet, mut complete: impl FnMut(&Candle, usize) -> bool) -> Result<Vec<Candle>, String> {
+    let mut out = Vec::new();
+    let mut current: Option<Candle> = None;
+    let mut start = 0;
+    let mut merged = 0usize;
+    for c in &dataset.candles {
+        if current.is_none() {
+            start = resample::parse_ts(&c.ts_utc)?;
+            merged = 0;
+        }
+        merged += 1;
+        let bar = resample::merge_candle(current.take(), c, start, merged);
+        if complete(&bar, merged) {
+            out.push(bar);
+        } else {
+            current = Some(bar);
+        }
+    }
+    out.extend(current);
+    Ok(out)
+}
+
+/// Range bars spanning exactly `range` from low to high, each opening at
+/// the previous close. Source bars only carry their extremes, so each is
+/// walked open, nearer extreme, farther extreme, close, and a long source
+/// bar completes as many range bars as its move covers. Volumes go to the
+/// bar a source bar starts in.
+fn range_bars(dataset: &DataSet, range: f64) -> Result<Vec<Candle>, String> {
+    let mut out = Vec::new();
+    let mut current: Option<Candle> = None;
+    let mut start = 0;
+    let mut merged = 0usize;
+    for c in &dataset.candles {
+        let ts = resample::parse_ts(&c.ts_utc)?;
+        let mut bar = match current.take() {
+            Some(acc) => {
+                merged += 1;
+                let (high, low, close) = (acc.high, acc.low, acc.close);
+                let mut bar = resample::merge_candle(Some(acc), c, start, merged);
+                bar.high = high;
+                bar.low = low;
+                bar.close = close;
+                bar
+            }
+            None => {
+                start = ts;
+                merged = 1;
+                let mut bar = resample::merge_candle(None, c, ts, merged);
+                bar.high = c.open;
+                bar.low = c.open;
+                bar.close = c.open;
+                bar
+            }
+        };
+        let path = if c.close >= c.open {
+            [c.open, c.low, c.high, c.close]
+        } else {
+            [c.open, c.high, c.low, c.close]
+        };
+        for price in path {
+            loop {
+                let close = if price - bar.low >= range {
+                    bar.low + range
+                } else if bar.high - price >= range {
+                    bar.high - range
+                } else {
+                    bar.high = bar.high.max(price);
+                    bar.low = bar.low.min(price);
+                    bar.close = price;
+                    break;
+                };
+                bar.high = bar.high.max(close);
+                bar.low = bar.low.min(close);
+                bar.close = close;
+                let next = Candle {
+                    ts_utc: c.ts_utc.clone(),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 0.0,
+                    tick_volume: c.tick_volume.map(|_| 0.0),
+                    real_volume: c.real_volume.map(|_| 0.0),
+                    spread: None,
+                    extra: Default::default(),
+                    spread_avg: None,
+                    bar: None,
+                    filled: false,
+                };
+                out.push(std::mem::replace(&mut bar, next));
+                start = ts;
+                merged = 0;
+            }
+        }
+        current = Some(bar);
+    }
+    out.extend(current);
+    Ok(out)
+}
+
+/// Heikin-Ashi candles: the close averages the bar's prices and the open
+/// is the midpoint of the previous Heikin-Ashi body.
+pub fn heikin_ashi(candles: &[Candle]) -> Vec<Candle> {
+    let mut out: Vec<Candle> = Vec::with_capacity(candles.len());
+    for c in candles {
+        let close = (c.open + c.high + c.low + c.close) / 4.0;
+        let open = match out.last() {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => (c.open + c.close) / 2.0,
+        };
+        out.push(Candle {
+            open,
+            high: c.high.max(open).max(close),
+            low: c.low.min(open).min(close),
+            close,
+            ..c.clone()
+        });
+    }
+    out
+}
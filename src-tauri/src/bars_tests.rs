@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use super::super::bars::{build, heikin_ashi, BarType};
+    use super::super::core::{Candle, DataSet};
+
+    fn dataset(bars: &[(f64, f64, f64)]) -> DataSet {
+        DataSet {
+            source_path: String::new(),
+            candles: bars
+                .iter()
+                .enumerate()
+                .map(|(i, (low, high, ticks))| Candle {
+                    ts_utc: format!("2015-03-18T14:{:02}:00Z", i),
+                    open: *low,
+                    high: *high,
+                    low: *low,
+                    close: *high,
+                    volume: ticks * 10.0,
+                    tick_volume: Some(*ticks),
+                    ..Default::default()
+                })
+                .collect(),
+            timeframe: None,
+        }
+    }
+
+    fn opens(out: &DataSet) -> Vec<&str> {
+        out.candles.iter().map(|c| &c.ts_utc[14..16]).collect()
+    }
+
+    #[test]
+    fn tick_and_volume_bars_fill_to_the_threshold() {
+        let data = dataset(&[(1.1, 1.1, 3.0), (1.1, 1.1, 4.0), (1.1, 1.1, 5.0), (1.1, 1.1, 1.0)]);
+        let ticks = build(&data, &BarType::parse("tick:7").unwrap()).unwrap();
+        assert_eq!(ticks.timeframe.as_deref(), Some("TICK:7"));
+        assert_eq!(opens(&ticks), vec!["00", "02"]);
+        assert_eq!(ticks.candles[0].tick_volume, Some(7.0));
+        assert_eq!(ticks.candles[1].tick_volume, Some(6.0));
+
+        let volume = build(&data, &BarType::parse("VOL:60").unwrap()).unwrap();
+        assert_eq!(opens(&volume), vec!["00", "02"]);
+        assert_eq!(volume.candles[0].volume, 70.0);
+    }
+
+    #[test]
+    fn tick_rows_without_tick_volume_count_rows() {
+        let mut data = dataset(&[(1.1, 1.1, 0.0); 5]);
+        for c in &mut data.candles {
+            c.tick_volume = None;
+            c.volume = 0.0;
+        }
+        let ticks = build(&data, &BarType::parse("TICK:2").unwrap()).unwrap();
+        assert_eq!(opens(&ticks), vec!["00", "02", "04"]);
+    }
+
+    #[test]
+    fn range_bars_are_cut_at_the_range() {
+        let data = dataset(&[(1.1000, 1.1004, 1.0), (1.1002, 1.1013, 1.0), (1.1010, 1.1012, 1.0)]);
+        let out = build(&data, &BarType::parse("RANGE:10").unwrap()).unwrap();
+        // the second source bar completes the first range bar and opens the next
+        assert_eq!(opens(&out), vec!["00", "01"]);
+        let first = &out.candles[0];
+        assert_eq!(((first.high - first.low) * 1e4).round(), 10.0);
+        assert_eq!(first.close, first.high);
+        assert_eq!(out.candles[1].open, first.close);
+    }
+
+    #[test]
+    fn long_source_bars_complete_several_range_bars() {
+        // one bar rising 2.5 ranges (RANGE:50 is 0.5 at this price)
+        let data = dataset(&[(100.0, 101.25, 1.0)]);
+        let out = build(&data, &BarType::parse("RANGE:50").unwrap()).unwrap();
+        let prices: Vec<(f64, f64, f64, f64)> = out.candles.iter().map(|c| (c.open, c.low, c.high, c.close)).collect();
+        assert_eq!(
+            prices,
+            vec![
+                (100.0, 100.0, 100.5, 100.5),
+                (100.5, 100.5, 101.0, 101.0),
+                (101.0, 101.0, 101.25, 101.25),
+            ]
+        );
+        assert_eq!(opens(&out), vec!["00", "00", "00"]);
+        assert_eq!(out.candles[0].volume, 10.0);
+        assert_eq!(out.candles[1].volume, 0.0);
+    }
+
+    #[test]
+    fn bar_labels_are_validated() {
+        assert_eq!(BarType::parse("range:2.5").unwrap().label(), "RANGE:2.5");
+        assert_eq!(BarType::parse("Tick:500").unwrap().label(), "TICK:500");
+        assert!(BarType::parse("TICK:2.5").is_err());
+        assert!(BarType::parse("VOL:0").is_err());
+        assert!(BarType::parse("BOX:10").is_err());
+    }
+
+    #[test]
+    fn bar_labels_round_trip() {
+        for label in [
+            "H1",
+            "D1@Europe/London+08:00",
+            "D1@America/New_York+17:00",
+            "H1@Etc/GMT+5+00:00",
+            "HA:D1@America/New_York+17:00",
+            "FILL:FLAT:H1",
+            "FILL:FFILL/WEEKEND:D1@Europe/London+08:00",
+            "RENKO:ATR14:WICK",
+            "RANGE:2.5",
+            "TICK:500",
+            "VOL:1000",
+        ] {
+            let bar_type = BarType::parse(label).unwrap();
+            assert_eq!(BarType::parse(&bar_type.label()).unwrap(), bar_type, "{}", label);
+        }
+    }
+
+    #[test]
+    fn heikin_ashi_wraps_other_bar_types() {
+        let data = dataset(&[(1.0, 2.0, 1.0), (2.0, 4.0, 1.0)]);
+        let ha = heikin_ashi(&data.candles);
+        assert_eq!((ha[0].open, ha[0].close), (1.5, 1.5));
+        assert_eq!((ha[1].open, ha[1].close), (1.5, 3.0));
+        assert_eq!((ha[1].low, ha[1].high), (1.5, 4.0));
+
+        let m2 = build(&data, &BarType::parse("ha:m2").unwrap()).unwrap();
+        assert_eq!(m2.timeframe.as_deref(), Some("HA:M2"));
+        assert_eq!(m2.candles.len(), 1);
+        assert_eq!(m2.candles[0].close, 2.5);
+        assert_eq!(BarType::parse("HA").unwrap().label(), "HA");
+        assert!(BarType::parse("HA:HA:M2").is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::cache;
+use crate::columnar;
+use crate::core;
+use crate::logger;
+use crate::presets::{self, Preset};
+use crate::schema;
+
+/// Bump when the bundle tables change; older readers refuse newer bundles.
+pub const BUNDLE_FORMAT: i32 = 1;
+
+const BUNDLE_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS bundle_meta (key TEXT PRIMARY KEY, value TEXT);\n\
+     CREATE TABLE IF NOT EXISTS bundle_datasets (\n\
+       source_path TEXT PRIMARY KEY,\n\
+       source_bytes INTEGER,\n\
+       bars INTEGER,\n\
+       timeframes TEXT,\n\
+       cache BLOB\n\
+     );\n\
+     CREATE TABLE IF NOT EXISTS bundle_presets (name TEXT PRIMARY KEY, data TEXT);";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundleExportOptions {
+    pub dest_path: String,
+    pub source_paths: Vec<String>,
+    #[serde(default)]
+    pub include_presets: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundleImportOptions {
+    pub bundle_path: String,
+    /// Original source path -> local copy of the same file. Datasets without
+    /// an entry keep their original path, detached from any CSV unless that
+    /// path exists here too.
+    #[serde(default)]
+    pub relink: BTreeMap<String, String>,
+    #[serde(default)]
+    pub include_presets: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundledDataset {
+    pub source_path: String,
+    /// Size of the source file when bundled, checked when relinking; 0 when
+    /// it was already detached.
+    pub source_bytes: u64,
+    pub bars: usize,
+    /// Resample targets cached alongside the source bars.
+    pub timeframes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundleSummary {
+    pub path: String,
+    pub created_at: String,
+    pub datasets: Vec<BundledDataset>,
+    pub presets: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportedDataset {
+    pub original_path: String,
+    /// Path the dataset is now addressed by.
+    pub source_path: String,
+    /// `false` when no source file backs the cache on this machine.
+    pub linked: bool,
+    pub bars: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundleImportResult {
+    pub datasets: Vec<ImportedDataset>,
+    pub presets: usize,
+}
+
+pub fn export_bundle(app: &AppHandle, options: &BundleExportOptions) -> Result<BundleSummary, String> {
+    let start = std::time::Instant::now();
+    let dest = PathBuf::from(&options.dest_path);
+    let tmp = dest.with_extension("bundle.tmp");
+    let snapshot = dest.with_extension("snapshot.tmp");
+    let _ = fs::remove_file(&tmp);
+
+    let result = (|| {
+        let conn = create_bundle(&tmp)?;
+        for source_path in &options.source_paths {
+            if core::cached_bar_count(app, source_path)?.is_none() {
+                core::load_csv_or_tsv(app, source_path)?;
+            }
+            if !core::snapshot_cache(app, source_path, &snapshot)? {
+                return Err(format!("no cache for {}", source_path));
+            }
+            let data = fs::read(&snapshot).map_err(|e| e.to_string())?;
+            let _ = fs::remove_file(&snapshot);
+            let source_bytes = fs::metadata(source_path).map(|m| m.len()).unwrap_or(0);
+            add_dataset(&conn, source_path, source_bytes, &data)?;
+        }
+        if options.include_presets {
+            for preset in presets::list_presets(app)? {
+                add_preset(&conn, &preset)?;
+            }
+        }
+        Ok(())
+    })();
+    let _ = fs::remove_file(&snapshot);
+    if let Err(err) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+    fs::rename(&tmp, &dest).map_err(|e| e.to_string())?;
+
+    let summary = read_summary(&dest)?;
+    let _ = logger::log_event(
+        app,
+        &format!(
+            "bundle export {} datasets {}ms",
+            summary.datasets.len(),
+            start.elapsed().as_millis()
+        ),
+    );
+    Ok(summary)
+}
+
+pub fn import_bundle(app: &AppHandle, options: &BundleImportOptions) -> Result<BundleImportResult, String> {
+    let conn = open_bundle(Path::new(&options.bundle_path))?;
+    let mut datasets = Vec::new();
+    for bundled in list_datasets(&conn)? {
+        let original = bundled.source_path.clone();
+        let local = options
+            .relink
+            .get(&original)
+            .cloned()
+            .or_else(|| Path::new(&original).exists().then(|| original.clone()));
+        let (source_path, cache_path, linked) = match local {
+            Some(local) => {
+                let bytes = fs::metadata(&local).map_err(|e| format!("{}: {}", local, e))?.len();
+                if bundled.source_bytes != 0 && bytes != bundled.source_bytes {
+                    return Err(format!("{} does not match the bundled {}", local, original));
+                }
+                let cache_path = core::cache_path_for_source(app, &local)?
+                    .ok_or_else(|| format!("cannot cache {}", local))?;
+                (local, cache_path, true)
+            }
+            None => (original.clone(), core::detached_cache_path(app, &original)?, false),
+        };
+
+        if let Some(parent) = cache_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let tmp = cache_path.with_extension("sqlite.tmp");
+        fs::write(&tmp, read_cache_blob(&conn, &original)?).map_err(|e| e.to_string())?;
+        let bars = match relabel_cache(&tmp, &source_path) {
+            Ok(bars) => bars,
+            Err(err) => {
+                let _ = fs::remove_file(&tmp);
+                return Err(err);
+            }
+        };
+        core::install_cache_file(app, &tmp, &cache_path)?;
+        let _ = cache::touch(app, &cache_path, &source_path);
+        datasets.push(ImportedDataset {
+            original_path: original,
+            source_path,
+            linked,
+            bars,
+        });
+    }
+
+    let mut imported_presets = 0;
+    if options.include_presets {
+        for preset in list_presets(&conn)? {
+            presets::save_preset(app, preset)?;
+            imported_presets += 1;
+        }
+    }
+    let _ = logger::log_event(
+        app,
+        &format!("bundle import {} datasets {} presets", datasets.len(), imported_presets),
+    );
+    Ok(BundleImportResult {
+        datasets,
+        presets: imported_presets,
+    })
+}
+
+pub fn create_bundle(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    conn.execute_batch(BUNDLE_SCHEMA).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO bundle_meta (key, value) VALUES ('format', ?1), ('created_at', ?2)",
+        (
+            BUNDLE_FORMAT.to_string(),
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+pub fn open_bundle(path: &Path) -> Result<Connection, String> {
+    if !path.exists() {
+        return Err("bundle not found".to_string());
+    }
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let format: Option<String> = conn
+        .query_row("SELECT value FROM bundle_meta WHERE key = 'format'", [], |row| row.get(0))
+        .optional()
+        .map_err(|_| "not a bundle file".to_string())?;
+    match format.and_then(|f| f.parse::<i32>().ok()) {
+        Some(f) if f <= BUNDLE_FORMAT => Ok(conn),
+        Some(_) => Err("bundle was written by a newer version".to_string()),
+        None => Err("not a bundle file".to_string()),
+    }
+}
+
+/// Adds a cache snapshot, reading its bar count and resample targets so the
+/// bundle can be listed without unpacking it.
+pub fn add_dataset(conn: &Connection, source_path: &str, source_bytes: u64, cache: &[u8]) -> Result<(), String> {
+    let probe = std::env::temp_dir().join(format!(
+        "fxgui_bundle_{}_{}.sqlite",
+        std::process::id(),
+        blake3::hash(source_path.as_bytes())
+    ));
+    fs::write(&probe, cache).map_err(|e| e.to_string())?;
+    let info = (|| {
+        let snapshot = schema::open(&probe)?;
+        let bars = columnar::series_len(&snapshot, columnar::RAW_SERIES)?;
+        let mut stmt = snapshot
+            .prepare("SELECT target FROM resample_meta ORDER BY target")
+            .map_err(|e| e.to_string())?;
+        let timeframes = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>((bars, timeframes))
+    })();
+    let _ = fs::remove_file(&probe);
+    let (bars, timeframes) = info?;
+    conn.execute(
+        "INSERT OR REPLACE INTO bundle_datasets (source_path, source_bytes, bars, timeframes, cache)\n\
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        (
+            source_path,
+            source_bytes as i64,
+            bars as i64,
+            timeframes.join(","),
+            cache,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn add_preset(conn: &Connection, preset: &Preset) -> Result<(), String> {
+    let data = serde_json::to_string(preset).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO bundle_presets (name, data) VALUES (?1, ?2)",
+        (&preset.name, data),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn read_summary(path: &Path) -> Result<BundleSummary, String> {
+    let conn = open_bundle(path)?;
+    let created_at: String = conn
+        .query_row("SELECT value FROM bundle_meta WHERE key = 'created_at'", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    Ok(BundleSummary {
+        path: path.to_string_lossy().to_string(),
+        created_at,
+        datasets: list_datasets(&conn)?,
+        presets: list_presets(&conn)?.into_iter().map(|p| p.name).collect(),
+    })
+}
+
+fn list_datasets(conn: &Connection) -> Result<Vec<BundledDataset>, String> {
+    let mut stmt = conn
+        .prepare("SELECT source_path, source_bytes, bars, timeframes FROM bundle_datasets ORDER BY source_path")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let timeframes: String = row.get(3)?;
+            Ok(BundledDataset {
+                source_path: row.get(0)?,
+                source_bytes: row.get::<_, i64>(1)? as u64,
+                bars: row.get::<_, i64>(2)? as usize,
+                timeframes: timeframes
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn list_presets(conn: &Connection) -> Result<Vec<Preset>, String> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM bundle_presets ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    let mut presets = Vec::new();
+    for row in rows {
+        let data = row.map_err(|e| e.to_string())?;
+        presets.push(serde_json::from_str(&data).map_err(|e| e.to_string())?);
+    }
+    Ok(presets)
+}
+
+fn read_cache_blob(conn: &Connection, source_path: &str) -> Result<Vec<u8>, String> {
+    conn.query_row(
+        "SELECT cache FROM bundle_datasets WHERE source_path = ?1",
+        [source_path],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Brings an unpacked cache up to the current schema and points it at
+/// `source_path`. Returns its bar count.
+fn relabel_cache(path: &Path, source_path: &str) -> Result<usize, String> {
+    let conn = schema::open(path)?;
+    conn.execute("UPDATE dataset_meta SET source_path = ?1", [source_path])
+        .map_err(|e| e.to_string())?;
+    columnar::series_len(&conn, columnar::RAW_SERIES)
+}
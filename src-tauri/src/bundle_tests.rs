@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use super::super::bundle::{add_dataset, add_preset, create_bundle, open_bundle, read_summary, BUNDLE_FORMAT};
+    use super::super::columnar::{write_series, RAW_SERIES};
+    use super::super::core::Candle;
+    use super::super::presets::Preset;
+    use super::super::schema;
+    use std::path::PathBuf;
+
+    fn temp(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn cache_bytes(name: &str, bars: usize) -> Vec<u8> {
+        let path = temp(name);
+        {
+            let conn = schema::open(&path).unwrap();
+            conn.execute("INSERT INTO dataset_meta (source_path) VALUES ('C:/data/a.csv')", [])
+                .unwrap();
+            let candles: Vec<Candle> = (0..bars)
+                .map(|i| Candle {
+                    ts_utc: format!("2015-03-18T14:{:02}:00Z", i),
+                    open: 1.0,
+                    high: 1.0,
+                    low: 1.0,
+                    close: 1.0,
+                    volume: 1.0,
+                    ..Default::default()
+                })
+                .collect();
+            write_series(&conn, RAW_SERIES, &candles).unwrap();
+            write_series(&conn, "M5", &candles[..1]).unwrap();
+            conn.execute("INSERT INTO resample_meta (target, count) VALUES ('M5', 1)", [])
+                .unwrap();
+        }
+        let data = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        data
+    }
+
+    #[test]
+    fn bundle_lists_datasets_and_presets() {
+        let path = temp("bundle_summary.fxbundle");
+        {
+            let conn = create_bundle(&path).unwrap();
+            add_dataset(&conn, "C:/data/a.csv", 1234, &cache_bytes("bundle_a.sqlite", 7)).unwrap();
+            add_preset(
+                &conn,
+                &Preset {
+                    name: "two panes".to_string(),
+                    split: 2,
+                    panes: vec![],
+                },
+            )
+            .unwrap();
+        }
+        let summary = read_summary(&path).unwrap();
+        assert_eq!(summary.datasets.len(), 1);
+        let dataset = &summary.datasets[0];
+        assert_eq!(dataset.source_path, "C:/data/a.csv");
+        assert_eq!(dataset.source_bytes, 1234);
+        assert_eq!(dataset.bars, 7);
+        assert_eq!(dataset.timeframes, vec!["M5".to_string()]);
+        assert_eq!(summary.presets, vec!["two panes".to_string()]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn newer_and_foreign_files_are_rejected() {
+        let path = temp("bundle_newer.fxbundle");
+        {
+            let conn = create_bundle(&path).unwrap();
+            conn.execute(
+                "UPDATE bundle_meta SET value = ?1 WHERE key = 'format'",
+                [(BUNDLE_FORMAT + 1).to_string()],
+            )
+            .unwrap();
+        }
+        assert!(open_bundle(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+
+        let other = temp("bundle_foreign.sqlite");
+        schema::open(&other).unwrap();
+        assert_eq!(open_bundle(&other).unwrap_err(), "not a bundle file");
+        let _ = std::fs::remove_file(other);
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::core;
+use crate::logger;
+
+const DEFAULT_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Serialises read-modify-write cycles on the index file, which is touched
+/// from both command handlers and the background ingest thread.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheSettings {
+    /// Upper bound for all cache files together; 0 disables eviction.
+    pub max_bytes: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    pub file: String,
+    pub source_path: Option<String>,
+    pub bytes: u64,
+    pub last_access: i64,
+    /// The source file exists but has changed since this cache was written.
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EvictionReport {
+    pub orphans_removed: u64,
+    pub evicted: u64,
+    pub bytes_freed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct IndexEntry {
+    source_path: String,
+    last_access: i64,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    Ok(base.join("cache_settings.json"))
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(core::cache_dir(app)?.join("index.json"))
+}
+
+pub fn load_settings(app: &AppHandle) -> Result<CacheSettings, String> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(CacheSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+pub fn save_settings(app: &AppHandle, settings: CacheSettings) -> Result<EvictionReport, String> {
+    let path = settings_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    fs::write(&path, data).map_err(|e| e.to_string())?;
+    // a lowered limit applies right away
+    enforce_limit(app, None)
+}
+
+fn load_index(app: &AppHandle) -> Result<BTreeMap<String, IndexEntry>, String> {
+    let path = index_path(app)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    // a damaged index only loses access times, so start over instead of failing
+    Ok(serde_json::from_str(&data).unwrap_or_default())
+}
+
+fn save_index(app: &AppHandle, index: &BTreeMap<String, IndexEntry>) -> Result<(), String> {
+    let path = index_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    fs::write(&path, data).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name().map(|name| name.to_string_lossy().to_string())
+}
+
+/// Records that the cache file for `source_path` was just used.
+pub fn touch(app: &AppHandle, cache_path: &Path, source_path: &str) -> Result<(), String> {
+    let Some(name) = file_name(cache_path) else {
+        return Ok(());
+    };
+    let _guard = INDEX_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut index = load_index(app)?;
+    index.insert(
+        name,
+        IndexEntry {
+            source_path: source_path.to_string(),
+            last_access: chrono::Utc::now().timestamp(),
+        },
+    );
+    save_index(app, &index)
+}
+
+pub fn list_entries(app: &AppHandle) -> Result<Vec<CacheEntry>, String> {
+    let dir = core::cache_dir(app)?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let index = {
+        let _guard = INDEX_LOCK.lock().map_err(|e| e.to_string())?;
+        load_index(app)?
+    };
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("sqlite") {
+            continue;
+        }
+        let Some(name) = file_name(&path) else {
+            continue;
+        };
+        let meta = fs::metadata(&path).map_err(|e| e.to_string())?;
+        let (source_path, last_access) = match index.get(&name) {
+            Some(item) => (Some(item.source_path.clone()), item.last_access),
+            // caches written before the index existed: fall back to the file itself
+            None => (core::cached_source_path(&path), modified_secs(&meta)),
+        };
+        let orphaned = match &source_path {
+            Some(source) => is_orphaned(app, &path, source),
+            None => false,
+        };
+        entries.push(CacheEntry {
+            file: name,
+            source_path,
+            bytes: meta.len(),
+            last_access,
+            orphaned,
+        });
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_access));
+    Ok(entries)
+}
+
+fn modified_secs(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// A cache is orphaned when its source still exists but now hashes to a
+/// different cache file. Missing sources are not orphans: the drive may be
+/// unmounted, or the cache may be all that is left of the data.
+fn is_orphaned(app: &AppHandle, cache_path: &Path, source_path: &str) -> bool {
+    match core::cache_path_for_source(app, source_path) {
+        Ok(Some(current)) => file_name(&current) != file_name(cache_path),
+        _ => false,
+    }
+}
+
+pub fn delete_entry(app: &AppHandle, file: &str) -> Result<bool, String> {
+    // only bare cache file names are accepted, never paths
+    if file.contains(['/', '\\']) || !file.ends_with(".sqlite") {
+        return Err("invalid cache entry".to_string());
+    }
+    let path = core::cache_dir(app)?.join(file);
+    let removed = core::remove_cache_file(app, &path)?;
+    let _guard = INDEX_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut index = load_index(app)?;
+    if index.remove(file).is_some() {
+        save_index(app, &index)?;
+    }
+    Ok(removed)
+}
+
+/// Cache files `enforce_limit` removes, by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvictionPlan {
+    pub orphans: Vec<String>,
+    /// Least recently used first.
+    pub lru: Vec<String>,
+}
+
+/// Picks every orphan, then the least recently used entries until the rest
+/// fits `settings.max_bytes`. `keep` is never picked.
+pub fn plan_eviction(entries: &[CacheEntry], settings: &CacheSettings, keep: Option<&str>) -> EvictionPlan {
+    let mut plan = EvictionPlan::default();
+    let mut kept: Vec<&CacheEntry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry.orphaned && Some(entry.file.as_str()) != keep {
+            plan.orphans.push(entry.file.clone());
+        } else {
+            kept.push(entry);
+        }
+    }
+
+    if settings.max_bytes > 0 {
+        let mut total: u64 = kept.iter().map(|e| e.bytes).sum();
+        kept.sort_by_key(|e| e.last_access);
+        for entry in kept {
+            if total <= settings.max_bytes {
+                break;
+            }
+            if Some(entry.file.as_str()) == keep {
+                continue;
+            }
+            plan.lru.push(entry.file.clone());
+            total -= entry.bytes;
+        }
+    }
+    plan
+}
+
+/// Removes orphaned caches, then evicts least-recently-used entries until the
+/// cache fits the configured size. `keep` is never evicted.
+pub fn enforce_limit(app: &AppHandle, keep: Option<&Path>) -> Result<EvictionReport, String> {
+    let settings = load_settings(app)?;
+    let keep = keep.and_then(file_name);
+    let entries = list_entries(app)?;
+    let plan = plan_eviction(&entries, &settings, keep.as_deref());
+    let dir = core::cache_dir(app)?;
+    let bytes = |file: &String| entries.iter().find(|e| &e.file == file).map_or(0, |e| e.bytes);
+
+    let mut report = EvictionReport::default();
+    for file in &plan.orphans {
+        if core::remove_cache_file(app, &dir.join(file))? {
+            report.orphans_removed += 1;
+            report.bytes_freed += bytes(file);
+        }
+    }
+    for file in &plan.lru {
+        if core::remove_cache_file(app, &dir.join(file))? {
+            report.evicted += 1;
+            report.bytes_freed += bytes(file);
+        }
+    }
+
+    if report.orphans_removed + report.evicted > 0 {
+        let _guard = INDEX_LOCK.lock().map_err(|e| e.to_string())?;
+        let mut index = load_index(app)?;
+        index.retain(|name, _| {
+            entries.iter().any(|e| &e.file == name) && !plan.orphans.contains(name) && !plan.lru.contains(name)
+        });
+        save_index(app, &index)?;
+        let _ = logger::log_event(
+            app,
+            &format!(
+                "cache evict orphans={} lru={} freed={}B",
+                report.orphans_removed, report.evicted, report.bytes_freed
+            ),
+        );
+    }
+    Ok(report)
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use super::super::cache::{plan_eviction, CacheEntry, CacheSettings, EvictionPlan};
+
+    fn entry(file: &str, bytes: u64, last_access: i64, orphaned: bool) -> CacheEntry {
+        CacheEntry {
+            file: file.to_string(),
+            source_path: Some(format!("/data/{}.csv", file)),
+            bytes,
+            last_access,
+            orphaned,
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used_until_under_limit() {
+        let entries = vec![
+            entry("new.sqlite", 40, 300, false),
+            entry("mid.sqlite", 40, 200, false),
+            entry("old.sqlite", 40, 100, false),
+        ];
+        let plan = plan_eviction(&entries, &CacheSettings { max_bytes: 80 }, None);
+        assert_eq!(
+            plan,
+            EvictionPlan {
+                orphans: vec![],
+                lru: vec!["old.sqlite".to_string()],
+            }
+        );
+        let plan = plan_eviction(&entries, &CacheSettings { max_bytes: 40 }, None);
+        assert_eq!(plan.lru, ["old.sqlite", "mid.sqlite"]);
+        // no limit, nothing to evict
+        assert_eq!(plan_eviction(&entries, &CacheSettings { max_bytes: 0 }, None), EvictionPlan::default());
+    }
+
+    #[test]
+    fn orphans_are_removed_before_counting_the_size() {
+        let entries = vec![
+            entry("a.sqlite", 40, 300, false),
+            entry("orphan.sqlite", 100, 400, true),
+            entry("b.sqlite", 40, 100, false),
+        ];
+        let plan = plan_eviction(&entries, &CacheSettings { max_bytes: 80 }, None);
+        assert_eq!(plan.orphans, ["orphan.sqlite"]);
+        assert!(plan.lru.is_empty());
+    }
+
+    #[test]
+    fn kept_entry_is_never_evicted() {
+        let entries = vec![
+            entry("a.sqlite", 40, 300, false),
+            entry("keep.sqlite", 40, 100, true),
+            entry("b.sqlite", 40, 200, false),
+        ];
+        let plan = plan_eviction(&entries, &CacheSettings { max_bytes: 40 }, Some("keep.sqlite"));
+        assert!(plan.orphans.is_empty());
+        assert_eq!(plan.lru, ["b.sqlite", "a.sqlite"]);
+
+        // the kept entry alone is over the limit: it stays anyway
+        let plan = plan_eviction(&entries[1..2], &CacheSettings { max_bytes: 10 }, Some("keep.sqlite"));
+        assert_eq!(plan, EvictionPlan::default());
+    }
+}
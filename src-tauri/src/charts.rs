@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use super::core::Candle;
+use super::renko::pip_size;
+
+/// Prices a Point & Figure chart reacts to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PointFigureMethod {
+    #[default]
+    Close,
+    /// Highs extend X columns and lows O columns, checked before reversals.
+    HighLow,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PointFigureOptions {
+    pub box_pips: f64,
+    /// Boxes the price must travel against a column to start the next one.
+    #[serde(default = "default_reversal")]
+    pub reversal: usize,
+    #[serde(default)]
+    pub method: PointFigureMethod,
+}
+
+fn default_reversal() -> usize {
+    3
+}
+
+/// One column of Xs (rising) or Os (falling), spanning `boxes` boxes from
+/// `low` to `high`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PointFigureColumn {
+    pub rising: bool,
+    pub low: f64,
+    pub high: f64,
+    pub boxes: usize,
+    pub start_ts: String,
+    pub end_ts: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PointFigureChart {
+    /// Box size in price units.
+    pub box_size: f64,
+    pub reversal: usize,
+    pub columns: Vec<PointFigureColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KagiOptions {
+    pub reversal_pips: f64,
+}
+
+/// A vertical Kagi line. `yang` lines (thick) have broken above the
+/// previous shoulder, `yin` lines (thin) below the previous waist; the flag
+/// holds the state at the end of the line.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KagiLine {
+    pub rising: bool,
+    pub start: f64,
+    pub end: f64,
+    pub yang: bool,
+    pub start_ts: String,
+    pub end_ts: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LineBreakOptions {
+    /// Lines a reversal has to break, three for the classic chart.
+    #[serde(default = "default_lines")]
+    pub lines: usize,
+}
+
+fn default_lines() -> usize {
+    3
+}
+
+/// One line (block) of a line break chart, from `open` to `close`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakLine {
+    pub rising: bool,
+    pub open: f64,
+    pub close: f64,
+    pub ts_utc: String,
+}
+
+/// Column in box indices, `price = index * box_size`.
+struct BoxColumn {
+    rising: bool,
+    low: i64,
+    high: i64,
+    start: usize,
+    end: usize,
+}
+
+pub fn point_figure(candles: &[Candle], options: &PointFigureOptions) -> Result<PointFigureChart, String> {
+    if !(options.box_pips.is_finite() && options.box_pips > 0.0) {
+        return Err("box size must be positive".to_string());
+    }
+    if options.reversal == 0 {
+        return Err("reversal must be at least one box".to_string());
+    }
+    let box_size = match candles.first() {
+        Some(first) => options.box_pips * pip_size(first.close),
+        None => options.box_pips,
+    };
+    let reversal = options.reversal as i64;
+    let mut columns: Vec<BoxColumn> = Vec::new();
+    let mut anchor = None;
+
+    for (i, c) in candles.iter().enumerate() {
+        let (up_price, down_price) = match options.method {
+            PointFigureMethod::Close => (c.close, c.close),
+            PointFigureMethod::HighLow => (c.high, c.low),
+        };
+        // boxes fully covered by the move in each direction
+        let up = (up_price / box_size + 1e-9).floor() as i64;
+        let down = (down_price / box_size - 1e-9).ceil() as i64;
+        let column = match columns.last_mut() {
+            Some(column) => column,
+            None => {
+                let start = *anchor.get_or_insert((c.close / box_size).floor() as i64);
+                if up > start {
+                    columns.push(BoxColumn { rising: true, low: start, high: up, start: 0, end: i });
+                } else if down < start {
+                    columns.push(BoxColumn { rising: false, low: down, high: start, start: 0, end: i });
+                }
+                continue;
+            }
+        };
+        if column.rising {
+            if up > column.high {
+                column.high = up;
+                column.end = i;
+            } else if down <= column.high - reversal {
+                let high = column.high - 1;
+                columns.push(BoxColumn { rising: false, low: down, high, start: i, end: i });
+            }
+        } else if down < column.low {
+            column.low = down;
+            column.end = i;
+        } else if up >= column.low + reversal {
+            let low = column.low + 1;
+            columns.push(BoxColumn { rising: true, low, high: up, start: i, end: i });
+        }
+    }
+
+    Ok(PointFigureChart {
+        box_size,
+        reversal: options.reversal,
+        columns: columns
+            .into_iter()
+            .map(|column| PointFigureColumn {
+                rising: column.rising,
+                low: column.low as f64 * box_size,
+                high: column.high as f64 * box_size,
+                boxes: (column.high - column.low + 1) as usize,
+                start_ts: candles[column.start].ts_utc.clone(),
+                end_ts: candles[column.end].ts_utc.clone(),
+            })
+            .collect(),
+    })
+}
+
+/// Kagi lines from closes. A line turns once the close moves the reversal
+/// amount against it.
+pub fn kagi(candles: &[Candle], options: &KagiOptions) -> Result<Vec<KagiLine>, String> {
+    if !(options.reversal_pips.is_finite() && options.reversal_pips > 0.0) {
+        return Err("reversal amount must be positive".to_string());
+    }
+    let first = match candles.first() {
+        Some(first) => first,
+        None => return Ok(Vec::new()),
+    };
+    let reversal = options.reversal_pips * pip_size(first.close);
+    let mut lines: Vec<KagiLine> = Vec::new();
+    let mut yang = false;
+    // last shoulder (top of a rising line) and waist (bottom of a falling one)
+    let mut shoulder = None;
+    let mut waist = None;
+
+    for c in &candles[1..] {
+        let price = c.close;
+        match lines.last_mut() {
+            None => {
+                if (price - first.close).abs() >= reversal {
+                    yang = price > first.close;
+                    lines.push(KagiLine {
+                        rising: price > first.close,
+                        start: first.close,
+                        end: price,
+                        yang,
+                        start_ts: first.ts_utc.clone(),
+                        end_ts: c.ts_utc.clone(),
+                    });
+                }
+            }
+            Some(line) => {
+                let extends = if line.rising { price > line.end } else { price < line.end };
+                if extends {
+                    line.end = price;
+                    line.end_ts = c.ts_utc.clone();
+                } else if (line.end - price).abs() >= reversal {
+                    if line.rising {
+                        shoulder = Some(line.end);
+                    } else {
+                        waist = Some(line.end);
+                    }
+                    let rising = !line.rising;
+                    let start = line.end;
+                    let start_ts = line.end_ts.clone();
+                    lines.push(KagiLine {
+                        rising,
+                        start,
+                        end: price,
+                        yang,
+                        start_ts,
+                        end_ts: c.ts_utc.clone(),
+                    });
+                } else {
+                    continue;
+                }
+                let line = lines.last_mut().expect("line pushed or extended above");
+                if line.rising && shoulder.is_some_and(|s| line.end > s) {
+                    yang = true;
+                } else if !line.rising && waist.is_some_and(|w| line.end < w) {
+                    yang = false;
+                }
+                line.yang = yang;
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// N-line break from closes: a close beyond the last line adds a line in
+/// its direction, a reversal needs a close beyond all of the last `lines`.
+pub fn line_break(candles: &[Candle], options: &LineBreakOptions) -> Result<Vec<BreakLine>, String> {
+    if options.lines == 0 {
+        return Err("line count must be at least one".to_string());
+    }
+    let first = match candles.first() {
+        Some(first) => first,
+        None => return Ok(Vec::new()),
+    };
+    let mut out: Vec<BreakLine> = Vec::new();
+    for c in &candles[1..] {
+        let price = c.close;
+        let line = match out.last() {
+            None if price != first.close => BreakLine {
+                rising: price > first.close,
+                open: first.close,
+                close: price,
+                ts_utc: c.ts_utc.clone(),
+            },
+            None => continue,
+            Some(last) => {
+                let recent = &out[out.len().saturating_sub(options.lines)..];
+                let top = recent.iter().map(|l| l.open.max(l.close)).fold(f64::MIN, f64::max);
+                let bottom = recent.iter().map(|l| l.open.min(l.close)).fold(f64::MAX, f64::min);
+                let (rising, open) = if last.rising && price > last.close {
+                    (true, last.close)
+                } else if last.rising && price < bottom {
+                    (false, last.open)
+                } else if !last.rising && price < last.close {
+                    (false, last.close)
+                } else if !last.rising && price > top {
+                    (true, last.open)
+                } else {
+                    continue;
+                };
+                BreakLine {
+                    rising,
+                    open,
+                    close: price,
+                    ts_utc: c.ts_utc.clone(),
+                }
+            }
+        };
+        out.push(line);
+    }
+    Ok(out)
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use super::super::charts::{
+        kagi, line_break, point_figure, KagiOptions, LineBreakOptions, PointFigureMethod,
+        PointFigureOptions,
+    };
+    use super::super::core::Candle;
+
+    fn candles(closes: &[f64]) -> Vec<Candle> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| Candle {
+                ts_utc: format!("2015-03-{:02}T00:00:00Z", i + 1),
+                open: *close,
+                high: *close,
+                low: *close,
+                close: *close,
+                volume: 0.0,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn point_figure_reverses_after_three_boxes() {
+        // 1 pip is 0.01 at these prices
+        let options = PointFigureOptions {
+            box_pips: 100.0,
+            reversal: 3,
+            method: PointFigureMethod::Close,
+        };
+        let data = candles(&[100.0, 103.5, 101.5, 100.0, 98.2, 99.0, 101.9, 102.0]);
+        let chart = point_figure(&data, &options).unwrap();
+        assert_eq!(chart.box_size, 1.0);
+        let columns: Vec<(bool, f64, f64)> = chart
+            .columns
+            .iter()
+            .map(|c| (c.rising, c.low, c.high))
+            .collect();
+        assert_eq!(columns, vec![(true, 100.0, 103.0), (false, 99.0, 102.0), (true, 100.0, 102.0)]);
+        assert_eq!(chart.columns[1].boxes, 4);
+        assert_eq!(chart.columns[1].start_ts, "2015-03-04T00:00:00Z");
+        assert_eq!(chart.columns[1].end_ts, "2015-03-05T00:00:00Z");
+    }
+
+    #[test]
+    fn kagi_turns_yang_above_the_last_shoulder() {
+        let options = KagiOptions { reversal_pips: 100.0 };
+        let lines = kagi(&candles(&[100.0, 102.0, 103.0, 101.5, 104.0, 100.0]), &options).unwrap();
+        let shape: Vec<(bool, f64, f64, bool)> =
+            lines.iter().map(|l| (l.rising, l.start, l.end, l.yang)).collect();
+        assert_eq!(
+            shape,
+            vec![
+                (true, 100.0, 103.0, true),
+                (false, 103.0, 101.5, true),
+                (true, 101.5, 104.0, true),
+                (false, 104.0, 100.0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn three_line_break_needs_to_clear_three_lines() {
+        let options = LineBreakOptions { lines: 3 };
+        let data = candles(&[1.0, 2.0, 3.0, 4.0, 2.5, 0.5, 5.0]);
+        let lines = line_break(&data, &options).unwrap();
+        let shape: Vec<(bool, f64, f64)> = lines.iter().map(|l| (l.rising, l.open, l.close)).collect();
+        assert_eq!(
+            shape,
+            vec![(true, 1.0, 2.0), (true, 2.0, 3.0), (true, 3.0, 4.0), (false, 3.0, 0.5), (true, 3.0, 5.0)]
+        );
+    }
+}
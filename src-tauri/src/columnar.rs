@@ -0,0 +1,484 @@
+use rusqlite::Connection;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use super::core::{BarStatus, Candle};
+use super::resample;
+
+/// Candles per block. Range reads decode whole blocks, so this bounds the
+/// over-read of a seek as well as the memory held while streaming.
+pub const BLOCK_SIZE: usize = 4096;
+
+/// Block format written; 2 added the spread mean, bar status and filled
+/// columns. Older blocks still decode.
+const FORMAT: u8 = 2;
+const ZSTD_LEVEL: i32 = 3;
+/// Bit pattern standing in for a missing optional value (a quiet NaN no
+/// parser produces).
+const NONE_BITS: u64 = 0x7ff8_0000_dead_beef;
+
+/// Series name of the raw candles in `candle_blocks`; resamples use their
+/// target label.
+pub const RAW_SERIES: &str = "";
+
+pub const BLOCKS_TABLE: &str = "CREATE TABLE IF NOT EXISTS candle_blocks (\n\
+       series TEXT,\n\
+       block INTEGER,\n\
+       start_idx INTEGER,\n\
+       count INTEGER,\n\
+       first_ts TEXT,\n\
+       last_ts TEXT,\n\
+       data BLOB,\n\
+       PRIMARY KEY (series, block)\n\
+     );\n\
+     CREATE INDEX IF NOT EXISTS idx_candle_blocks_ts ON candle_blocks(series, first_ts, last_ts);";
+
+pub struct Block {
+    /// Bar index of the first candle in the block.
+    pub start_idx: usize,
+    pub candles: Vec<Candle>,
+}
+
+/// Encodes candles column by column: timestamps as second deltas, floats XORed
+/// with their predecessor and split into byte planes, then zstd over the lot.
+pub fn encode_candles(candles: &[Candle]) -> Result<Vec<u8>, String> {
+    let n = candles.len();
+    let mut raw = Vec::with_capacity(n * 48);
+    raw.push(FORMAT);
+    put_varint(&mut raw, n as u64);
+
+    let mut prev = 0i64;
+    for c in candles {
+        let ts = resample::parse_ts(&c.ts_utc)?;
+        put_varint(&mut raw, zigzag(ts - prev));
+        prev = ts;
+    }
+
+    put_floats(&mut raw, candles.iter().map(|c| c.open.to_bits()), n);
+    put_floats(&mut raw, candles.iter().map(|c| c.high.to_bits()), n);
+    put_floats(&mut raw, candles.iter().map(|c| c.low.to_bits()), n);
+    put_floats(&mut raw, candles.iter().map(|c| c.close.to_bits()), n);
+    put_floats(&mut raw, candles.iter().map(|c| c.volume.to_bits()), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.tick_volume), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.real_volume), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.spread), n);
+
+    // extra columns are stored per key, missing where a row lacks the key
+    let keys: BTreeSet<&String> = candles.iter().flat_map(|c| c.extra.keys()).collect();
+    put_varint(&mut raw, keys.len() as u64);
+    for key in keys {
+        put_varint(&mut raw, key.len() as u64);
+        raw.extend_from_slice(key.as_bytes());
+        let bits = candles.iter().map(|c| c.extra.get(key).map_or(NONE_BITS, |v| v.to_bits()));
+        put_floats(&mut raw, bits, n);
+    }
+
+    put_optional(&mut raw, candles.iter().map(|c| c.spread_avg), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.bar.map(|b| b.source_bars as f64)), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.bar.and_then(|b| b.expected_bars).map(|v| v as f64)), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.bar.map(|b| if b.complete { 1.0 } else { 0.0 })), n);
+    put_optional(&mut raw, candles.iter().map(|c| c.filled.then_some(1.0)), n);
+
+    zstd::encode_all(raw.as_slice(), ZSTD_LEVEL).map_err(|e| e.to_string())
+}
+
+pub fn decode_candles(data: &[u8]) -> Result<Vec<Candle>, String> {
+    let raw = zstd::decode_all(data).map_err(|e| e.to_string())?;
+    let mut r = Reader::new(&raw)?;
+    let n = r.varint()? as usize;
+
+    let mut ts = Vec::with_capacity(n);
+    let mut prev = 0i64;
+    for _ in 0..n {
+        prev += unzigzag(r.varint()?);
+        ts.push(resample::format_ts(prev));
+    }
+
+    let open = r.floats(n)?;
+    let high = r.floats(n)?;
+    let low = r.floats(n)?;
+    let close = r.floats(n)?;
+    let volume = r.floats(n)?;
+    let tick_volume = r.optional(n)?;
+    let real_volume = r.optional(n)?;
+    let spread = r.optional(n)?;
+    let mut extras = vec![BTreeMap::new(); n];
+    for _ in 0..r.varint()? {
+        let len = r.varint()? as usize;
+        let key = String::from_utf8(r.take(len)?.to_vec()).map_err(|e| e.to_string())?;
+        for (extra, bits) in extras.iter_mut().zip(r.floats(n)?) {
+            if bits != NONE_BITS {
+                extra.insert(key.clone(), f64::from_bits(bits));
+            }
+        }
+    }
+    let (spread_avg, source_bars, expected_bars, complete, filled) = if r.format >= 2 {
+        (r.optional(n)?, r.optional(n)?, r.optional(n)?, r.optional(n)?, r.optional(n)?)
+    } else {
+        (None, None, None, None, None)
+    };
+    let at = |column: &Option<Vec<Option<f64>>>, i: usize| column.as_ref().and_then(|v| v[i]);
+
+    Ok(ts
+        .into_iter()
+        .zip(extras)
+        .enumerate()
+        .map(|(i, (ts_utc, extra))| Candle {
+            ts_utc,
+            open: f64::from_bits(open[i]),
+            high: f64::from_bits(high[i]),
+            low: f64::from_bits(low[i]),
+            close: f64::from_bits(close[i]),
+            volume: f64::from_bits(volume[i]),
+            tick_volume: at(&tick_volume, i),
+            real_volume: at(&real_volume, i),
+            spread: at(&spread, i),
+            extra,
+            spread_avg: at(&spread_avg, i),
+            bar: at(&source_bars, i).map(|n| BarStatus {
+                source_bars: n as usize,
+                expected_bars: at(&expected_bars, i).map(|v| v as usize),
+                complete: at(&complete, i) == Some(1.0),
+            }),
+            filled: at(&filled, i).is_some(),
+        })
+        .collect())
+}
+
+/// Encodes an indicator series with the same float scheme as the candles.
+pub fn encode_values(values: &[Option<f64>]) -> Result<Vec<u8>, String> {
+    let mut raw = Vec::with_capacity(values.len() * 8 + 8);
+    raw.push(FORMAT);
+    put_varint(&mut raw, values.len() as u64);
+    put_optional(&mut raw, values.iter().copied(), values.len());
+    zstd::encode_all(raw.as_slice(), ZSTD_LEVEL).map_err(|e| e.to_string())
+}
+
+pub fn decode_values(data: &[u8]) -> Result<Vec<Option<f64>>, String> {
+    let raw = zstd::decode_all(data).map_err(|e| e.to_string())?;
+    let mut r = Reader::new(&raw)?;
+    let n = r.varint()? as usize;
+    Ok(r.optional(n)?.unwrap_or_else(|| vec![None; n]))
+}
+
+/// Replaces `series` with `candles`, split into blocks.
+pub fn write_series(conn: &Connection, series: &str, candles: &[Candle]) -> Result<(), String> {
+    conn.execute("DELETE FROM candle_blocks WHERE series = ?1", [series])
+        .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT INTO candle_blocks (series, block, start_idx, count, first_ts, last_ts, data)\n\
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .map_err(|e| e.to_string())?;
+    for (block, chunk) in candles.chunks(BLOCK_SIZE).enumerate() {
+        stmt.execute((
+            series,
+            block as i64,
+            (block * BLOCK_SIZE) as i64,
+            chunk.len() as i64,
+            &chunk[0].ts_utc,
+            &chunk[chunk.len() - 1].ts_utc,
+            encode_candles(chunk)?,
+        ))
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn series_len(conn: &Connection, series: &str) -> Result<usize, String> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(count), 0) FROM candle_blocks WHERE series = ?1",
+            [series],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(count as usize)
+}
+
+/// Visits, in order, the blocks holding bars `from..to`. `f` returns `false`
+/// to stop early.
+pub fn blocks_by_index<F>(conn: &Connection, series: &str, from: usize, to: usize, f: F) -> Result<(), String>
+where
+    F: FnMut(Block) -> Result<bool, String>,
+{
+    query_blocks(
+        conn,
+        "start_idx + count > ?2 AND start_idx < ?3 ORDER BY block ASC",
+        (series, from as i64, to.min(i64::MAX as usize) as i64),
+        f,
+    )
+}
+
+/// Visits, in order, the blocks overlapping `from_ts..=to_ts`.
+pub fn blocks_by_time<F>(conn: &Connection, series: &str, from_ts: &str, to_ts: &str, f: F) -> Result<(), String>
+where
+    F: FnMut(Block) -> Result<bool, String>,
+{
+    query_blocks(
+        conn,
+        "last_ts >= ?2 AND first_ts <= ?3 ORDER BY block ASC",
+        (series, from_ts, to_ts),
+        f,
+    )
+}
+
+/// Visits the blocks starting at or before `ts`, newest first.
+pub fn blocks_ending_at<F>(conn: &Connection, series: &str, ts: &str, f: F) -> Result<(), String>
+where
+    F: FnMut(Block) -> Result<bool, String>,
+{
+    query_blocks(conn, "first_ts <= ?2 ORDER BY block DESC", (series, ts), f)
+}
+
+/// The block that would hold the last bar at or before `ts` (`before`) or the
+/// first bar at or after it.
+pub fn seek_block(conn: &Connection, series: &str, ts: &str, before: bool) -> Result<Option<Block>, String> {
+    let clause = if before {
+        "first_ts <= ?2 ORDER BY block DESC LIMIT 1"
+    } else {
+        "last_ts >= ?2 ORDER BY block ASC LIMIT 1"
+    };
+    let mut found = None;
+    query_blocks(conn, clause, (series, ts), |block| {
+        found = Some(block);
+        Ok(false)
+    })?;
+    Ok(found)
+}
+
+fn query_blocks<P, F>(conn: &Connection, clause: &str, params: P, mut f: F) -> Result<(), String>
+where
+    P: rusqlite::Params,
+    F: FnMut(Block) -> Result<bool, String>,
+{
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT start_idx, data FROM candle_blocks WHERE series = ?1 AND {}",
+            clause
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params).map_err(|e| e.to_string())?;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let start_idx: i64 = row.get(0).map_err(|e| e.to_string())?;
+        let data: Vec<u8> = row.get(1).map_err(|e| e.to_string())?;
+        let block = Block {
+            start_idx: start_idx as usize,
+            candles: decode_candles(&data)?,
+        };
+        if !f(block)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Column list of the row-per-candle layout used before the block format.
+pub const ROW_COLUMNS: &str =
+    "ts_utc, open, high, low, close, volume, tick_volume, real_volume, spread, extra";
+
+pub fn candle_from_row(row: &rusqlite::Row) -> rusqlite::Result<Candle> {
+    let extra: Option<String> = row.get(9)?;
+    let extra = match extra {
+        Some(json) => serde_json::from_str(&json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        None => BTreeMap::new(),
+    };
+    Ok(Candle {
+        ts_utc: row.get(0)?,
+        open: row.get(1)?,
+        high: row.get(2)?,
+        low: row.get(3)?,
+        close: row.get(4)?,
+        volume: row.get(5)?,
+        tick_volume: row.get(6)?,
+        real_volume: row.get(7)?,
+        spread: row.get(8)?,
+        extra,
+        spread_avg: None,
+        bar: None,
+        filled: false,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutTiming {
+    pub write_ms: u128,
+    pub read_ms: u128,
+    pub bytes: u64,
+}
+
+/// Writes `candles` in the row-per-candle layout to `path`, reads them back
+/// and removes the file, for comparing against the block layout.
+pub fn bench_row_layout(path: &Path, candles: &[Candle]) -> Result<LayoutTiming, String> {
+    let _ = std::fs::remove_file(path);
+    let write_start = std::time::Instant::now();
+    {
+        let mut conn = Connection::open(path).map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(
+            "CREATE TABLE candles (ts_utc TEXT, open REAL, high REAL, low REAL, close REAL, volume REAL,\n\
+               tick_volume REAL, real_volume REAL, spread REAL, extra TEXT);\n\
+             CREATE INDEX idx_candles_ts ON candles(ts_utc);",
+        )
+        .map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "INSERT INTO candles ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    ROW_COLUMNS
+                ))
+                .map_err(|e| e.to_string())?;
+            for c in candles {
+                let extra = if c.extra.is_empty() {
+                    None
+                } else {
+                    serde_json::to_string(&c.extra).ok()
+                };
+                stmt.execute((
+                    &c.ts_utc,
+                    c.open,
+                    c.high,
+                    c.low,
+                    c.close,
+                    c.volume,
+                    c.tick_volume,
+                    c.real_volume,
+                    c.spread,
+                    extra,
+                ))
+                .map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    let write_ms = write_start.elapsed().as_millis();
+    let bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    let read_start = std::time::Instant::now();
+    let read = {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM candles ORDER BY ROWID ASC", ROW_COLUMNS))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], candle_from_row).map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    };
+    let read_ms = read_start.elapsed().as_millis();
+    let _ = std::fs::remove_file(path);
+    read?;
+    Ok(LayoutTiming { write_ms, read_ms, bytes })
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// XORs each value with the previous one and stores byte plane by byte plane,
+/// so the unchanged sign/exponent bytes of neighbouring prices become runs of
+/// zeros for zstd.
+fn put_floats(out: &mut Vec<u8>, bits: impl Iterator<Item = u64>, n: usize) {
+    let start = out.len();
+    out.resize(start + n * 8, 0);
+    let mut prev = 0u64;
+    for (i, b) in bits.enumerate() {
+        let x = b ^ prev;
+        prev = b;
+        for plane in 0..8 {
+            out[start + plane * n + i] = (x >> (plane * 8)) as u8;
+        }
+    }
+}
+
+fn put_optional(out: &mut Vec<u8>, values: impl Iterator<Item = Option<f64>> + Clone, n: usize) {
+    if values.clone().all(|v| v.is_none()) {
+        out.push(0);
+        return;
+    }
+    out.push(1);
+    put_floats(out, values.map(|v| v.map_or(NONE_BITS, f64::to_bits)), n);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: u8,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Result<Reader<'a>, String> {
+        let mut r = Reader { data, pos: 0, format: 0 };
+        r.format = r.byte()?;
+        if !(1..=FORMAT).contains(&r.format) {
+            return Err("unsupported cache block format".to_string());
+        }
+        Ok(r)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "truncated cache block".to_string())?;
+        let out = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err("invalid varint in cache block".to_string())
+    }
+
+    fn floats(&mut self, n: usize) -> Result<Vec<u64>, String> {
+        let planes = self.take(n.checked_mul(8).ok_or("truncated cache block")?)?;
+        let mut prev = 0u64;
+        Ok((0..n)
+            .map(|i| {
+                let mut x = 0u64;
+                for plane in 0..8 {
+                    x |= (planes[plane * n + i] as u64) << (plane * 8);
+                }
+                prev ^= x;
+                prev
+            })
+            .collect())
+    }
+
+    fn optional(&mut self, n: usize) -> Result<Option<Vec<Option<f64>>>, String> {
+        if self.byte()? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.floats(n)?
+                .into_iter()
+                .map(|b| if b == NONE_BITS { None } else { Some(f64::from_bits(b)) })
+                .collect(),
+        ))
+    }
+}
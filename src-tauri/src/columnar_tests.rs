@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use super::super::columnar::{
+        blocks_by_index, decode_candles, decode_values, encode_candles, encode_values, seek_block,
+        series_len, write_series, BLOCKS_TABLE, BLOCK_SIZE,
+    };
+    use super::super::core::{BarStatus, Candle};
+    use std::collections::BTreeMap;
+
+    fn candle(i: i64) -> Candle {
+        let mut extra = BTreeMap::new();
+        if i % 3 == 0 {
+            extra.insert("ask".to_string(), 1.1 + i as f64 * 1e-5);
+        }
+        Candle {
+            ts_utc: format!("2015-03-18T{:02}:{:02}:00Z", i / 60 % 24, i % 60),
+            open: 1.1 + i as f64 * 1e-5,
+            high: 1.2,
+            low: 1.0,
+            close: 1.15 - i as f64 * 1e-5,
+            volume: i as f64,
+            tick_volume: if i % 2 == 0 { Some(i as f64) } else { None },
+            spread: Some(1.5),
+            extra,
+            spread_avg: if i % 2 == 0 { Some(1.25) } else { None },
+            bar: (i % 4 == 0).then_some(BarStatus {
+                source_bars: i as usize,
+                expected_bars: if i % 8 == 0 { Some(60) } else { None },
+                complete: i % 12 != 0,
+            }),
+            filled: i % 5 == 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn candles_round_trip_through_a_block() {
+        let candles: Vec<Candle> = (0..500).map(candle).collect();
+        let decoded = decode_candles(&encode_candles(&candles).unwrap()).unwrap();
+        assert_eq!(decoded.len(), candles.len());
+        for (a, b) in candles.iter().zip(&decoded) {
+            assert_eq!(a.ts_utc, b.ts_utc);
+            assert_eq!(a.open.to_bits(), b.open.to_bits());
+            assert_eq!(a.close.to_bits(), b.close.to_bits());
+            assert_eq!(a.tick_volume, b.tick_volume);
+            assert_eq!(a.real_volume, b.real_volume);
+            assert_eq!(a.spread, b.spread);
+            assert_eq!(a.extra, b.extra);
+            assert_eq!(a.spread_avg, b.spread_avg);
+            assert_eq!(a.bar, b.bar);
+            assert_eq!(a.filled, b.filled);
+        }
+    }
+
+    #[test]
+    fn values_keep_gaps() {
+        let values = vec![None, Some(1.5), Some(f64::NAN), None, Some(-2.0)];
+        let decoded = decode_values(&encode_values(&values).unwrap()).unwrap();
+        assert_eq!(decoded.len(), values.len());
+        assert_eq!(decoded[0], None);
+        assert_eq!(decoded[1], Some(1.5));
+        assert!(decoded[2].unwrap().is_nan());
+        assert_eq!(decoded[4], Some(-2.0));
+    }
+
+    #[test]
+    fn truncated_blocks_are_rejected() {
+        let data = encode_candles(&[candle(1)]).unwrap();
+        assert!(decode_candles(&data[..data.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn range_reads_seek_to_blocks() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(BLOCKS_TABLE).unwrap();
+        let candles: Vec<Candle> = (0..(BLOCK_SIZE as i64 + 10))
+            .map(|i| {
+                let mut c = candle(0);
+                c.ts_utc = format!("2015-03-{:02}T{:02}:{:02}:00Z", 1 + i / 1440, i / 60 % 24, i % 60);
+                c
+            })
+            .collect();
+        write_series(&conn, "", &candles).unwrap();
+        assert_eq!(series_len(&conn, "").unwrap(), candles.len());
+
+        let mut starts = Vec::new();
+        blocks_by_index(&conn, "", BLOCK_SIZE + 2, BLOCK_SIZE + 4, |block| {
+            starts.push(block.start_idx);
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(starts, vec![BLOCK_SIZE]);
+
+        let last = &candles[BLOCK_SIZE + 5].ts_utc;
+        let block = seek_block(&conn, "", last, true).unwrap().unwrap();
+        assert_eq!(block.start_idx, BLOCK_SIZE);
+        let block = seek_block(&conn, "", "2015-03-01T00:00:30Z", false).unwrap().unwrap();
+        assert_eq!(block.start_idx, 0);
+        assert!(seek_block(&conn, "", "2016-01-01T00:00:00Z", false).unwrap().is_none());
+    }
+}
@@ -1,25 +1,78 @@
 use serde::{Deserialize, Serialize};
 use rusqlite::OptionalExtension;
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
+use crate::bars;
+use crate::cache;
+use crate::columnar::{self, RAW_SERIES};
 use crate::logger;
+use crate::mtf;
+use crate::pool::{self, CachePool, PooledConnection};
+use crate::schema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Candle {
     pub ts_utc: String,
+    #[serde(deserialize_with = "nan_if_null")]
     pub open: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub high: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub low: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub close: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub volume: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_volume: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub real_volume: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spread: Option<f64>,
+    /// Numeric columns without a dedicated field, keyed by header name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, f64>,
+    /// Mean spread of the source bars merged into this one; `spread` keeps
+    /// the widest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spread_avg: Option<f64>,
+    /// How much of a resampled bar the source covers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar: Option<BarStatus>,
+    /// Inserted into a gap rather than read from the source.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub filled: bool,
+}
+
+/// Gap filling with `NAN` leaves NaN prices, which JSON carries as `null`.
+/// Reading `null` back as NaN lets those bars round-trip through the
+/// frontend.
+fn nan_if_null<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BarStatus {
+    /// Source bars merged into the bar.
+    pub source_bars: usize,
+    /// Source bars a gapless bar holds, when the source interval is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_bars: Option<usize>,
+    /// Whether the source covers the bar to its close. Only the last bar
+    /// of a series can be incomplete.
+    pub complete: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DataSet {
     pub source_path: String,
     pub candles: Vec<Candle>,
+    /// Resample target the candles were built at; `None` for the source bars.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeframe: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,7 +88,7 @@ pub struct CacheStatus {
     pub bytes: u64,
 }
 
-fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+pub fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
     let base = app
         .path()
         .app_data_dir()
@@ -57,20 +110,109 @@ fn cache_path(app: &AppHandle, key: &str) -> Result<PathBuf, String> {
     Ok(cache_dir(app)?.join(format!("{}.sqlite", blake3::hash(key.as_bytes()))))
 }
 
+/// Cache location for a dataset imported without its source file. The key
+/// has no mtime, so the cache stays addressable by the original path.
+pub fn detached_cache_path(app: &AppHandle, source_path: &str) -> Result<PathBuf, String> {
+    cache_path(app, &format!("detached_{}", source_path))
+}
+
+/// Quarantined files kept for inspection; older ones are deleted.
+const QUARANTINE_KEEP: usize = 5;
+
+fn cache_conn(app: &AppHandle, path: &Path) -> Result<PooledConnection, String> {
+    let result = app.state::<CachePool>().get(path);
+    if let Err(err) = &result {
+        // moving the file aside makes the next ingest rebuild it from the source
+        if pool::is_corrupt(err) {
+            let _ = quarantine_cache_file(app, path, err);
+        }
+    }
+    result
+}
+
+/// Moves a damaged cache file into `cache/quarantine` so it no longer
+/// shadows the source data, keeping the newest few for inspection.
+pub fn quarantine_cache_file(app: &AppHandle, path: &Path, reason: &str) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let dir = cache_dir(app)?.join("quarantine");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dest = dir.join(format!("{}.{}", name, chrono::Utc::now().timestamp()));
+    app.state::<CachePool>().evict(path);
+    fs::rename(path, &dest).map_err(|e| e.to_string())?;
+    remove_cache_file(app, path)?;
+    let _ = logger::log_event(
+        app,
+        &format!("cache quarantined {} {}", dest.to_string_lossy(), reason),
+    );
+
+    let mut quarantined: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    if quarantined.len() > QUARANTINE_KEEP {
+        quarantined.sort_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok());
+        let excess = quarantined.len() - QUARANTINE_KEEP;
+        for old in quarantined.into_iter().take(excess) {
+            let _ = fs::remove_file(old);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a cache file along with its WAL sidecars, closing pooled
+/// connections to it first.
+pub fn remove_cache_file(app: &AppHandle, path: &Path) -> Result<bool, String> {
+    app.state::<CachePool>().evict(path);
+    remove_sidecars(path)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(path).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+fn remove_sidecars(path: &Path) -> Result<(), String> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let sidecar = PathBuf::from(sidecar);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
 pub fn clear_cache(app: &AppHandle) -> Result<u64, String> {
     let dir = cache_dir(app)?;
     if !dir.exists() {
         return Ok(0);
     }
+    app.state::<CachePool>().clear();
     let mut removed = 0;
     for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("sqlite") {
-            fs::remove_file(&path).map_err(|e| e.to_string())?;
-            removed += 1;
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("sqlite") => {
+                remove_cache_file(app, &path)?;
+                removed += 1;
+            }
+            // left behind by a write interrupted before its rename
+            Some("tmp") => fs::remove_file(&path).map_err(|e| e.to_string())?,
+            _ => {}
         }
     }
+    let index = dir.join("index.json");
+    if index.exists() {
+        fs::remove_file(&index).map_err(|e| e.to_string())?;
+    }
     Ok(removed)
 }
 
@@ -150,8 +292,19 @@ pub fn record_dataset_history(app: &AppHandle, path: &str) -> Result<(), String>
 pub fn load_csv_or_tsv(app: &AppHandle, path: &str) -> Result<IngestResult, String> {
     let start = std::time::Instant::now();
     let _ = logger::log_event(app, &format!("ingest start {}", path));
+    let source_path = path;
     let path = PathBuf::from(path);
     if !path.exists() {
+        // datasets imported from a bundle without their CSV live on in the cache
+        if let Some(cache_path) = cache_path_for_source(app, source_path)? {
+            let dataset = load_from_cache(app, &cache_path)?;
+            let _ = logger::log_event(app, "ingest detached cache hit");
+            let _ = cache::touch(app, &cache_path, &dataset.source_path);
+            return Ok(IngestResult {
+                dataset,
+                used_cache: true,
+            });
+        }
         let _ = logger::log_event(app, "ingest error file not found");
         return Err("file not found".to_string());
     }
@@ -161,20 +314,31 @@ pub fn load_csv_or_tsv(app: &AppHandle, path: &str) -> Result<IngestResult, Stri
 
     if cache_path.exists() {
         let cache_start = std::time::Instant::now();
-        let dataset = load_from_cache(&cache_path)?;
-        let _ = logger::log_event(
-            app,
-            &format!("ingest cache load {}ms", cache_start.elapsed().as_millis()),
-        );
-        let _ = logger::log_event(app, "ingest cache hit");
-        let _ = logger::log_event(
-            app,
-            &format!("ingest total {}ms", start.elapsed().as_millis()),
-        );
-        return Ok(IngestResult {
-            dataset,
-            used_cache: true,
-        });
+        match load_from_cache(app, &cache_path) {
+            Ok(dataset) => {
+                let _ = logger::log_event(
+                    app,
+                    &format!("ingest cache load {}ms", cache_start.elapsed().as_millis()),
+                );
+                let _ = logger::log_event(app, "ingest cache hit");
+                let _ = cache::touch(app, &cache_path, &dataset.source_path);
+                let _ = cache::enforce_limit(app, Some(&cache_path));
+                let _ = logger::log_event(
+                    app,
+                    &format!("ingest total {}ms", start.elapsed().as_millis()),
+                );
+                return Ok(IngestResult {
+                    dataset,
+                    used_cache: true,
+                });
+            }
+            Err(err) => {
+                // corrupt files are already quarantined by now; anything else
+                // (e.g. emptied by a schema rebuild) is just dropped
+                let _ = logger::log_event(app, &format!("ingest cache unreadable {}, rebuilding", err));
+                remove_cache_file(app, &cache_path)?;
+            }
+        }
     }
 
     let parse_start = std::time::Instant::now();
@@ -184,15 +348,15 @@ pub fn load_csv_or_tsv(app: &AppHandle, path: &str) -> Result<IngestResult, Stri
         &format!("ingest parse {}ms", parse_start.elapsed().as_millis()),
     );
     let cache_write_start = std::time::Instant::now();
-    save_to_cache(&cache_path, &dataset)?;
-    let _ = logger::log_event(
-        app,
-        &format!(
-            "ingest cache write {}ms",
-            cache_write_start.elapsed().as_millis()
-        ),
-    );
+    save_to_cache(app, &cache_path, &dataset)?;
+    let cache_write_ms = cache_write_start.elapsed().as_millis();
+    let _ = logger::log_event(app, &format!("ingest cache write {}ms", cache_write_ms));
+    if cfg!(debug_assertions) {
+        log_layout_benchmark(app, &cache_path, &dataset, cache_write_ms);
+    }
     let _ = logger::log_event(app, "ingest success");
+    let _ = cache::touch(app, &cache_path, &dataset.source_path);
+    let _ = cache::enforce_limit(app, Some(&cache_path));
     let _ = logger::log_event(
         app,
         &format!("ingest total {}ms", start.elapsed().as_millis()),
@@ -212,16 +376,28 @@ pub fn cached_bar_count(app: &AppHandle, source_path: &str) -> Result<Option<usi
     if !cache_path.exists() {
         return Ok(None);
     }
-    let conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM candles", [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
-    Ok(Some(count as usize))
+    let conn = cache_conn(app, &cache_path)?;
+    let count = columnar::series_len(&conn, RAW_SERIES)?;
+    // a cache rebuilt by a schema change holds no candles until re-ingested
+    if count == 0 {
+        return Ok(None);
+    }
+    Ok(Some(count))
 }
 
+/// Reads the source path recorded inside a cache file.
+pub fn cached_source_path(cache_path: &Path) -> Option<String> {
+    let conn = rusqlite::Connection::open(cache_path).ok()?;
+    conn.query_row("SELECT source_path FROM dataset_meta LIMIT 1", [], |row| row.get(0))
+        .ok()
+}
+
+/// Reads bars `offset..offset + limit` of the source, or of the `target`
+/// resample when given.
 pub fn load_range_from_cache(
     app: &AppHandle,
     source_path: &str,
+    target: Option<&str>,
     offset: usize,
     limit: usize,
 ) -> Result<Option<Vec<Candle>>, String> {
@@ -232,44 +408,412 @@ pub fn load_range_from_cache(
     if !cache_path.exists() {
         return Ok(None);
     }
-    let conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT ts_utc, open, high, low, close, volume\n\
-             FROM candles ORDER BY ROWID ASC LIMIT ?1 OFFSET ?2",
-        )
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map((limit as i64, offset as i64), |row| {
-            Ok(Candle {
-                ts_utc: row.get(0)?,
-                open: row.get(1)?,
-                high: row.get(2)?,
-                low: row.get(3)?,
-                close: row.get(4)?,
-                volume: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
+    let conn = cache_conn(app, &cache_path)?;
+    let end = offset.saturating_add(limit);
     let mut candles = Vec::new();
-    for row in rows {
-        candles.push(row.map_err(|e| e.to_string())?);
-    }
+    columnar::blocks_by_index(&conn, target.unwrap_or(RAW_SERIES), offset, end, |block| {
+        let skip = offset.saturating_sub(block.start_idx);
+        let take = end - (block.start_idx + skip);
+        candles.extend(block.candles.into_iter().skip(skip).take(take));
+        Ok(true)
+    })?;
     Ok(Some(candles))
 }
 
-fn cache_path_for_source(app: &AppHandle, source_path: &str) -> Result<Option<PathBuf>, String> {
+pub fn cache_path_for_source(app: &AppHandle, source_path: &str) -> Result<Option<PathBuf>, String> {
     if source_path.trim().is_empty() {
         return Ok(None);
     }
     let path = PathBuf::from(source_path);
     if !path.exists() {
-        return Ok(None);
+        let detached = detached_cache_path(app, source_path)?;
+        return Ok(detached.exists().then_some(detached));
     }
     let key = cache_key(&path)?;
     Ok(Some(cache_path(app, &key)?))
 }
 
+pub fn resample_cache_count(
+    app: &AppHandle,
+    source_path: &str,
+    target: &str,
+) -> Result<Option<usize>, String> {
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    if !cache_path.exists() {
+        return Ok(None);
+    }
+    let conn = cache_conn(app, &cache_path)?;
+    let count: Option<i64> = conn
+        .query_row(
+            "SELECT count FROM resample_meta WHERE target = ?1",
+            [target],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(count.filter(|v| *v > 0).map(|v| v as usize))
+}
+
+/// Streams cached candles in order without collecting them, reading the
+/// resample cache for `target` or the raw candles when it is `None`.
+/// Returns `false` when the source has no cached bars to read from.
+pub fn for_each_cached_candle<F>(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+    mut f: F,
+) -> Result<bool, String>
+where
+    F: FnMut(usize, Candle) -> Result<(), String>,
+{
+    let conn = match series_conn(app, source_path, target)? {
+        Some(conn) => conn,
+        None => return Ok(false),
+    };
+    columnar::blocks_by_index(&conn, target.unwrap_or(RAW_SERIES), 0, usize::MAX, |block| {
+        for (i, candle) in block.candles.into_iter().enumerate() {
+            f(block.start_idx + i, candle)?;
+        }
+        Ok(true)
+    })?;
+    Ok(true)
+}
+
+/// `for_each_cached_candle` over the candles with `from <= ts_utc <= to`,
+/// decoding only the blocks that overlap the range.
+pub fn for_each_cached_candle_between<F>(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+    mut f: F,
+) -> Result<bool, String>
+where
+    F: FnMut(usize, Candle) -> Result<(), String>,
+{
+    let conn = match series_conn(app, source_path, target)? {
+        Some(conn) => conn,
+        None => return Ok(false),
+    };
+    columnar::blocks_by_time(&conn, target.unwrap_or(RAW_SERIES), from, to, |block| {
+        for (i, candle) in block.candles.into_iter().enumerate() {
+            if candle.ts_utc.as_str() > to {
+                return Ok(false);
+            }
+            if candle.ts_utc.as_str() >= from {
+                f(block.start_idx + i, candle)?;
+            }
+        }
+        Ok(true)
+    })?;
+    Ok(true)
+}
+
+/// The cache connection of `source_path` when it holds bars for `target`.
+fn series_conn(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+) -> Result<Option<PooledConnection>, String> {
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+    let conn = cache_conn(app, &cache_path)?;
+    if columnar::series_len(&conn, target.unwrap_or(RAW_SERIES))? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(conn))
+}
+
+/// Maps a timeframe (or other bar type) label to its canonical resample
+/// cache target; `None`, empty and `raw` address the source bars.
+pub fn timeframe_target(timeframe: Option<&str>) -> Result<Option<String>, String> {
+    match timeframe.filter(|tf| !tf.is_empty() && !tf.eq_ignore_ascii_case("raw")) {
+        Some(tf) => Ok(Some(bars::BarType::parse(tf)?.label())),
+        None => Ok(None),
+    }
+}
+
+/// Makes sure the source (and `target` resample, if any) is in the cache,
+/// ingesting and resampling on a miss.
+pub fn ensure_cached(app: &AppHandle, source_path: &str, target: Option<&str>) -> Result<(), String> {
+    let base_cached = cached_bar_count(app, source_path)?.is_some();
+    let target_cached = match target {
+        Some(target) => resample_cache_count(app, source_path, target)?.is_some(),
+        None => true,
+    };
+    if base_cached && target_cached {
+        return Ok(());
+    }
+    let dataset = load_csv_or_tsv(app, source_path)?.dataset;
+    if let Some(target) = target {
+        let resampled = bars::build(&dataset, &bars::BarType::parse(target)?)?;
+        save_resample_cache(app, source_path, target, &resampled)?;
+    }
+    Ok(())
+}
+
+/// Loads the full source dataset, or its bars at `timeframe` (any label
+/// `timeframe_target` accepts), building and caching them on a miss.
+pub fn load_dataset(app: &AppHandle, source_path: &str, timeframe: Option<&str>) -> Result<DataSet, String> {
+    match timeframe_target(timeframe)? {
+        Some(target) => {
+            ensure_cached(app, source_path, Some(&target))?;
+            load_resample_cache(app, source_path, &target)?
+                .ok_or_else(|| "resampled data not cached".to_string())
+        }
+        None => Ok(load_csv_or_tsv(app, source_path)?.dataset),
+    }
+}
+
+/// Accepts `ts_utc` style (`2015-03-18T14:00:00Z`) or source style
+/// (`2015.03.18 14:00:00`) timestamps and returns the `ts_utc` form.
+pub fn normalize_query_ts(ts: &str) -> Result<String, String> {
+    let ts = ts.trim();
+    if ts.contains('T') {
+        let dt = chrono::DateTime::parse_from_rfc3339(ts)
+            .map_err(|_| format!("invalid timestamp: {}", ts))?;
+        return Ok(dt
+            .with_timezone(&chrono::Utc)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string());
+    }
+    normalize_timestamp(ts)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeekMode {
+    /// Closest bar on either side; ties go to the earlier bar.
+    Nearest,
+    /// Last bar at or before the timestamp.
+    Before,
+    /// First bar at or after the timestamp.
+    After,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BarPosition {
+    pub index: usize,
+    pub ts_utc: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimeRange {
+    /// Bar index of the first candle, usable as a `dataset_range` offset.
+    pub offset: usize,
+    pub candles: Vec<Candle>,
+}
+
+pub fn seek_one(
+    conn: &rusqlite::Connection,
+    target: Option<&str>,
+    ts: &str,
+    before: bool,
+) -> Result<Option<BarPosition>, String> {
+    let block = match columnar::seek_block(conn, target.unwrap_or(RAW_SERIES), ts, before)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let found = if before {
+        block.candles.iter().rposition(|c| c.ts_utc.as_str() <= ts)
+    } else {
+        block.candles.iter().position(|c| c.ts_utc.as_str() >= ts)
+    };
+    Ok(found.map(|i| BarPosition {
+        index: block.start_idx + i,
+        ts_utc: block.candles[i].ts_utc.clone(),
+    }))
+}
+
+fn epoch_of(ts_utc: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(ts_utc)
+        .ok()
+        .map(|dt| dt.timestamp())
+}
+
+pub fn find_bar_index(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+    ts: &str,
+    mode: SeekMode,
+) -> Result<Option<BarPosition>, String> {
+    let ts = normalize_query_ts(ts)?;
+    ensure_cached(app, source_path, target)?;
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let conn = cache_conn(app, &cache_path)?;
+    seek_bar(&conn, target, &ts, mode)
+}
+
+/// The bar `mode` picks for the normalized timestamp `ts`.
+pub fn seek_bar(
+    conn: &rusqlite::Connection,
+    target: Option<&str>,
+    ts: &str,
+    mode: SeekMode,
+) -> Result<Option<BarPosition>, String> {
+    match mode {
+        SeekMode::Before => seek_one(conn, target, ts, true),
+        SeekMode::After => seek_one(conn, target, ts, false),
+        SeekMode::Nearest => {
+            let before = seek_one(conn, target, ts, true)?;
+            let after = seek_one(conn, target, ts, false)?;
+            Ok(match (before, after) {
+                (Some(b), Some(a)) => {
+                    let at = epoch_of(ts).unwrap_or_default();
+                    let to_before = at - epoch_of(&b.ts_utc).unwrap_or(at);
+                    let to_after = epoch_of(&a.ts_utc).unwrap_or(at) - at;
+                    Some(if to_after < to_before { a } else { b })
+                }
+                (b, a) => b.or(a),
+            })
+        }
+    }
+}
+
+/// Opens the cache of `source_path` with both timeframes built, for
+/// mapping bars between them.
+fn mapping_conn(
+    app: &AppHandle,
+    source_path: &str,
+    lower: Option<&str>,
+    higher: &str,
+) -> Result<Option<PooledConnection>, String> {
+    ensure_cached(app, source_path, lower)?;
+    ensure_cached(app, source_path, Some(higher))?;
+    match cache_path_for_source(app, source_path)? {
+        Some(path) => Ok(Some(cache_conn(app, &path)?)),
+        None => Ok(None),
+    }
+}
+
+/// Lower timeframe index ranges of the higher timeframe bars
+/// `[offset, offset + limit)`; a `None` lower timeframe means the source bars.
+pub fn bar_spans(
+    app: &AppHandle,
+    source_path: &str,
+    lower: Option<&str>,
+    higher: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<mtf::BarSpan>, String> {
+    match mapping_conn(app, source_path, lower, higher)? {
+        Some(conn) => mtf::bar_spans(&conn, lower, higher, offset, limit),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub fn containing_bar(
+    app: &AppHandle,
+    source_path: &str,
+    lower: Option<&str>,
+    higher: &str,
+    lower_index: usize,
+) -> Result<Option<BarPosition>, String> {
+    match mapping_conn(app, source_path, lower, higher)? {
+        Some(conn) => mtf::containing_bar(&conn, lower, higher, lower_index),
+        None => Ok(None),
+    }
+}
+
+/// Candles with `from <= ts_utc <= to`, at most `limit` of them.
+pub fn load_time_range(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+    limit: usize,
+) -> Result<Option<TimeRange>, String> {
+    let from = normalize_query_ts(from)?;
+    let to = normalize_query_ts(to)?;
+    ensure_cached(app, source_path, target)?;
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let conn = cache_conn(app, &cache_path)?;
+    time_range(&conn, target, &from, &to, limit).map(Some)
+}
+
+pub fn time_range(
+    conn: &rusqlite::Connection,
+    target: Option<&str>,
+    from: &str,
+    to: &str,
+    limit: usize,
+) -> Result<TimeRange, String> {
+    let mut offset = None;
+    let mut candles = Vec::new();
+    columnar::blocks_by_time(conn, target.unwrap_or(RAW_SERIES), from, to, |block| {
+        for (i, candle) in block.candles.into_iter().enumerate() {
+            if candles.len() >= limit || candle.ts_utc.as_str() > to {
+                return Ok(false);
+            }
+            if candle.ts_utc.as_str() >= from {
+                offset.get_or_insert(block.start_idx + i);
+                candles.push(candle);
+            }
+        }
+        Ok(true)
+    })?;
+    Ok(TimeRange {
+        offset: offset.unwrap_or(0),
+        candles,
+    })
+}
+
+/// The `count` candles up to and including `ts`.
+pub fn load_bars_ending_at(
+    app: &AppHandle,
+    source_path: &str,
+    target: Option<&str>,
+    ts: &str,
+    count: usize,
+) -> Result<Option<TimeRange>, String> {
+    let ts = normalize_query_ts(ts)?;
+    ensure_cached(app, source_path, target)?;
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let conn = cache_conn(app, &cache_path)?;
+    bars_ending_at(&conn, target, &ts, count).map(Some)
+}
+
+pub fn bars_ending_at(
+    conn: &rusqlite::Connection,
+    target: Option<&str>,
+    ts: &str,
+    count: usize,
+) -> Result<TimeRange, String> {
+    let mut offset = 0;
+    // newest block first; chunks are reversed back into order at the end
+    let mut chunks = Vec::new();
+    let mut collected = 0;
+    columnar::blocks_ending_at(conn, target.unwrap_or(RAW_SERIES), ts, |block| {
+        let end = block.candles.partition_point(|c| c.ts_utc.as_str() <= ts);
+        let start = end - (count - collected).min(end);
+        collected += end - start;
+        offset = block.start_idx + start;
+        chunks.push(block.candles[start..end].to_vec());
+        Ok(collected < count)
+    })?;
+    Ok(TimeRange {
+        offset,
+        candles: chunks.into_iter().rev().flatten().collect(),
+    })
+}
+
 pub fn load_resample_cache(
     app: &AppHandle,
     source_path: &str,
@@ -282,22 +826,7 @@ pub fn load_resample_cache(
     if !cache_path.exists() {
         return Ok(None);
     }
-    let conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS resample_meta (target TEXT PRIMARY KEY, count INTEGER);\n\
-         CREATE TABLE IF NOT EXISTS resample_candles (\n\
-           target TEXT,\n\
-           idx INTEGER,\n\
-           ts_utc TEXT,\n\
-           open REAL,\n\
-           high REAL,\n\
-           low REAL,\n\
-           close REAL,\n\
-           volume REAL\n\
-         );\n\
-         CREATE INDEX IF NOT EXISTS idx_resample_target_idx ON resample_candles(target, idx);",
-    )
-    .map_err(|e| e.to_string())?;
+    let conn = cache_conn(app, &cache_path)?;
 
     let count: Option<i64> = conn
         .query_row(
@@ -312,29 +841,11 @@ pub fn load_resample_cache(
         _ => return Ok(None),
     };
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT ts_utc, open, high, low, close, volume\n\
-             FROM resample_candles WHERE target = ?1 ORDER BY idx ASC",
-        )
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([target], |row| {
-            Ok(Candle {
-                ts_utc: row.get(0)?,
-                open: row.get(1)?,
-                high: row.get(2)?,
-                low: row.get(3)?,
-                close: row.get(4)?,
-                volume: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
     let mut candles = Vec::with_capacity(count);
-    for row in rows {
-        candles.push(row.map_err(|e| e.to_string())?);
-    }
+    columnar::blocks_by_index(&conn, target, 0, usize::MAX, |block| {
+        candles.extend(block.candles);
+        Ok(true)
+    })?;
     if candles.len() != count {
         return Ok(None);
     }
@@ -342,6 +853,7 @@ pub fn load_resample_cache(
     Ok(Some(DataSet {
         source_path: source_path.to_string(),
         candles,
+        timeframe: Some(target.to_string()),
     }))
 }
 
@@ -358,50 +870,12 @@ pub fn save_resample_cache(
     if let Some(parent) = cache_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let mut conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS resample_meta (target TEXT PRIMARY KEY, count INTEGER);\n\
-         CREATE TABLE IF NOT EXISTS resample_candles (\n\
-           target TEXT,\n\
-           idx INTEGER,\n\
-           ts_utc TEXT,\n\
-           open REAL,\n\
-           high REAL,\n\
-           low REAL,\n\
-           close REAL,\n\
-           volume REAL\n\
-         );\n\
-         CREATE INDEX IF NOT EXISTS idx_resample_target_idx ON resample_candles(target, idx);",
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute("DELETE FROM resample_candles WHERE target = ?1", [target])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM resample_meta WHERE target = ?1", [target])
-        .map_err(|e| e.to_string())?;
+    let mut conn = cache_conn(app, &cache_path)?;
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-    {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO resample_candles (target, idx, ts_utc, open, high, low, close, volume)\n\
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            )
-            .map_err(|e| e.to_string())?;
-        for (idx, c) in dataset.candles.iter().enumerate() {
-            stmt.execute((
-                target,
-                idx as i64,
-                &c.ts_utc,
-                c.open,
-                c.high,
-                c.low,
-                c.close,
-                c.volume,
-            ))
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    tx.execute("DELETE FROM resample_meta WHERE target = ?1", [target])
+        .map_err(|e| e.to_string())?;
+    columnar::write_series(&tx, target, &dataset.candles)?;
     tx.execute(
         "INSERT INTO resample_meta (target, count) VALUES (?1, ?2)",
         (target, dataset.candles.len() as i64),
@@ -411,12 +885,23 @@ pub fn save_resample_cache(
     Ok(())
 }
 
+/// Indicator series kept per cache file; the least recently used beyond this
+/// are evicted on save.
+const MAX_INDICATOR_VARIANTS: i64 = 64;
+
+/// How stale `last_used` may get before a cache hit refreshes it; eviction
+/// only needs a rough recency order, so hits don't each cost a write.
+const INDICATOR_TOUCH_SECS: i64 = 3600;
+
+/// Reads the series stored under each of `keys` (see
+/// `IndicatorDescriptor::cache_key`), in order. Any miss or length mismatch
+/// makes the whole lookup a miss.
 pub fn load_indicator_cache(
     app: &AppHandle,
     source_path: &str,
     expected_len: usize,
-    indicators: &[&str],
-) -> Result<Option<serde_json::Value>, String> {
+    keys: &[String],
+) -> Result<Option<Vec<Vec<Option<f64>>>>, String> {
     let cache_path = match cache_path_for_source(app, source_path)? {
         Some(path) => path,
         None => return Ok(None),
@@ -424,63 +909,48 @@ pub fn load_indicator_cache(
     if !cache_path.exists() {
         return Ok(None);
     }
-    let conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS indicator_meta (indicator TEXT PRIMARY KEY, count INTEGER);\n\
-         CREATE TABLE IF NOT EXISTS indicator_values (\n\
-           indicator TEXT,\n\
-           idx INTEGER,\n\
-           value REAL\n\
-         );\n\
-         CREATE INDEX IF NOT EXISTS idx_indicator_idx ON indicator_values(indicator, idx);",
-    )
-    .map_err(|e| e.to_string())?;
+    let conn = cache_conn(app, &cache_path)?;
 
-    let mut result = serde_json::Map::new();
-    for name in indicators {
-        let count: Option<i64> = conn
+    let now = chrono::Utc::now().timestamp();
+    let mut result = Vec::with_capacity(keys.len());
+    let mut stale = Vec::new();
+    for key in keys {
+        let row: Option<(i64, Option<Vec<u8>>, Option<i64>)> = conn
             .query_row(
-                "SELECT count FROM indicator_meta WHERE indicator = ?1",
-                [*name],
-                |row| row.get(0),
+                "SELECT count, data, last_used FROM indicator_meta WHERE indicator = ?1",
+                [key],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
             .optional()
             .map_err(|e| e.to_string())?;
-        let count = match count {
-            Some(v) if v as usize == expected_len => v as usize,
+        let (data, last_used) = match row {
+            Some((count, Some(data), last_used)) if count as usize == expected_len => (data, last_used),
             _ => return Ok(None),
         };
-
-        let mut stmt = conn
-            .prepare(
-                "SELECT idx, value FROM indicator_values WHERE indicator = ?1 ORDER BY idx ASC",
-            )
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map([*name], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<f64>>(1)?)))
-            .map_err(|e| e.to_string())?;
-
-        let mut series = vec![serde_json::Value::Null; count];
-        for row in rows {
-            let (idx, value) = row.map_err(|e| e.to_string())?;
-            let idx = idx as usize;
-            if idx < series.len() {
-                series[idx] = match value {
-                    Some(v) => serde_json::Value::from(v),
-                    None => serde_json::Value::Null,
-                };
-            }
+        if last_used.is_none_or(|t| now - t >= INDICATOR_TOUCH_SECS) {
+            stale.push(key);
+        }
+        let values = columnar::decode_values(&data)?;
+        if values.len() != expected_len {
+            return Ok(None);
         }
-        result.insert((*name).to_string(), serde_json::Value::Array(series));
+        result.push(values);
     }
 
-    Ok(Some(serde_json::Value::Object(result)))
+    for key in stale {
+        conn.execute(
+            "UPDATE indicator_meta SET last_used = ?1 WHERE indicator = ?2",
+            (now, key),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(Some(result))
 }
 
 pub fn save_indicator_cache(
     app: &AppHandle,
     source_path: &str,
-    indicators: &[(&str, Vec<Option<f64>>)],
+    indicators: &[(String, Vec<Option<f64>>)],
 ) -> Result<(), String> {
     let cache_path = match cache_path_for_source(app, source_path)? {
         Some(path) => path,
@@ -489,45 +959,32 @@ pub fn save_indicator_cache(
     if let Some(parent) = cache_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let mut conn = rusqlite::Connection::open(cache_path).map_err(|e| e.to_string())?;
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS indicator_meta (indicator TEXT PRIMARY KEY, count INTEGER);\n\
-         CREATE TABLE IF NOT EXISTS indicator_values (\n\
-           indicator TEXT,\n\
-           idx INTEGER,\n\
-           value REAL\n\
-         );\n\
-         CREATE INDEX IF NOT EXISTS idx_indicator_idx ON indicator_values(indicator, idx);",
-    )
-    .map_err(|e| e.to_string())?;
+    let mut conn = cache_conn(app, &cache_path)?;
 
+    let now = chrono::Utc::now().timestamp();
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-    for (name, series) in indicators {
-        tx.execute("DELETE FROM indicator_values WHERE indicator = ?1", [*name])
-            .map_err(|e| e.to_string())?;
-        tx.execute("DELETE FROM indicator_meta WHERE indicator = ?1", [*name])
-            .map_err(|e| e.to_string())?;
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO indicator_values (indicator, idx, value) VALUES (?1, ?2, ?3)",
-            )
-            .map_err(|e| e.to_string())?;
-        for (idx, value) in series.iter().enumerate() {
-            stmt.execute((*name, idx as i64, value))
-                .map_err(|e| e.to_string())?;
-        }
+    for (key, series) in indicators {
         tx.execute(
-            "INSERT INTO indicator_meta (indicator, count) VALUES (?1, ?2)",
-            (*name, series.len() as i64),
+            "INSERT OR REPLACE INTO indicator_meta (indicator, count, data, last_used)\n\
+             VALUES (?1, ?2, ?3, ?4)",
+            (key, series.len() as i64, columnar::encode_values(series)?, now),
         )
         .map_err(|e| e.to_string())?;
     }
+    tx.execute(
+        "DELETE FROM indicator_meta WHERE indicator NOT IN (\n\
+           SELECT indicator FROM indicator_meta ORDER BY last_used DESC LIMIT ?1\n\
+         )",
+        [MAX_INDICATOR_VARIANTS.max(indicators.len() as i64)],
+    )
+    .map_err(|e| e.to_string())?;
     tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
 fn parse_csv_like(path: &Path) -> Result<DataSet, String> {
-    let (mode, header_present) = detect_mode_and_header(path)?;
+    let (mode, columns) = detect_mode_and_header(path)?;
+    let header_present = columns.is_some();
     let mut candles = Vec::new();
     match mode {
         ParseMode::Csv(delim) => {
@@ -546,7 +1003,7 @@ fn parse_csv_like(path: &Path) -> Result<DataSet, String> {
                     continue;
                 }
                 let fields: Vec<&str> = record.iter().collect();
-                let candle = parse_parts(&fields, idx + 1)?;
+                let candle = parse_parts(&fields, columns.as_ref(), idx + 1)?;
                 candles.push(candle);
             }
         }
@@ -563,7 +1020,7 @@ fn parse_csv_like(path: &Path) -> Result<DataSet, String> {
                     continue;
                 }
                 let parts: Vec<&str> = line.split_whitespace().collect();
-                let candle = parse_parts(&parts, idx + 1)?;
+                let candle = parse_parts(&parts, columns.as_ref(), idx + 1)?;
                 candles.push(candle);
             }
         }
@@ -572,6 +1029,7 @@ fn parse_csv_like(path: &Path) -> Result<DataSet, String> {
     Ok(DataSet {
         source_path: path.to_string_lossy().to_string(),
         candles,
+        timeframe: None,
     })
 }
 
@@ -580,7 +1038,7 @@ enum ParseMode {
     Whitespace,
 }
 
-fn detect_mode_and_header(path: &Path) -> Result<(ParseMode, bool), String> {
+fn detect_mode_and_header(path: &Path) -> Result<(ParseMode, Option<ColumnMap>), String> {
     let file = fs::File::open(path).map_err(|e| e.to_string())?;
     let reader = BufReader::new(file);
     for line in reader.lines() {
@@ -589,22 +1047,167 @@ fn detect_mode_and_header(path: &Path) -> Result<(ParseMode, bool), String> {
         if line.is_empty() {
             continue;
         }
-        let mode = if line.contains('\t') {
-            ParseMode::Csv(b'\t')
+        let (mode, fields): (ParseMode, Vec<&str>) = if line.contains('\t') {
+            (ParseMode::Csv(b'\t'), line.split('\t').collect())
         } else if line.contains(',') {
-            ParseMode::Csv(b',')
+            (ParseMode::Csv(b','), line.split(',').collect())
         } else {
-            ParseMode::Whitespace
+            (ParseMode::Whitespace, line.split_whitespace().collect())
         };
-        let lower = line.to_lowercase();
-        let header = lower.contains("timestamp") || (lower.contains("date") && lower.contains("time"));
-        return Ok((mode, header));
+        // a data row always carries numeric prices, a header row never does
+        let header = fields.iter().all(|f| parse_f64(f).is_err());
+        if !header {
+            return Ok((mode, None));
+        }
+        let columns = parse_header(&fields)?;
+        return Ok((mode, Some(columns)));
+    }
+    Ok((ParseMode::Whitespace, None))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Date,
+    Time,
+    DateTime,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    TickVolume,
+    Bid,
+    Ask,
+    Spread,
+}
+
+fn column_alias(name: &str) -> Option<Column> {
+    let key: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    match key.as_str() {
+        "date" | "day" => Some(Column::Date),
+        "time" => Some(Column::Time),
+        "datetime" | "timestamp" | "ts" | "tsutc" => Some(Column::DateTime),
+        "o" | "open" => Some(Column::Open),
+        "h" | "high" => Some(Column::High),
+        "l" | "low" => Some(Column::Low),
+        "c" | "close" => Some(Column::Close),
+        "v" | "vol" | "volume" | "realvolume" => Some(Column::Volume),
+        "tickvol" | "tickvolume" | "ticks" => Some(Column::TickVolume),
+        "bid" => Some(Column::Bid),
+        "ask" => Some(Column::Ask),
+        "spread" => Some(Column::Spread),
+        _ => None,
     }
-    Ok((ParseMode::Whitespace, false))
+}
+
+/// Column positions resolved from a header row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ColumnMap {
+    /// Column holding the date, or the whole timestamp when `time` is `None`.
+    date: usize,
+    time: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    tick_volume: Option<usize>,
+    real_volume: Option<usize>,
+    spread: Option<usize>,
+    /// Columns kept verbatim in `Candle::extra`, with their normalised names.
+    extra: Vec<(String, usize)>,
+    /// Number of header fields, used to spot timestamps split by whitespace.
+    width: usize,
+}
+
+fn parse_header(fields: &[&str]) -> Result<ColumnMap, String> {
+    let mut found: Vec<(Column, usize)> = Vec::new();
+    let mut unknown = Vec::new();
+    for (idx, field) in fields.iter().enumerate() {
+        match column_alias(field) {
+            Some(column) => {
+                if !found.iter().any(|(c, _)| *c == column) {
+                    found.push((column, idx));
+                }
+            }
+            None => unknown.push((field.trim().to_string(), idx)),
+        }
+    }
+    let find = |column: Column| found.iter().find(|(c, _)| *c == column).map(|(_, idx)| *idx);
+
+    let (date, time) = match (find(Column::DateTime), find(Column::Date), find(Column::Time)) {
+        (Some(ts), _, _) => (Some(ts), None),
+        (None, Some(date), time) => (Some(date), time),
+        (None, None, time) => (time, None),
+    };
+    // tick exports only carry bid/ask, so the bid price stands in for OHLC
+    let bid = find(Column::Bid);
+    let open = find(Column::Open).or(bid);
+    let high = find(Column::High).or(bid);
+    let low = find(Column::Low).or(bid);
+    let close = find(Column::Close).or(bid);
+
+    let mut missing = Vec::new();
+    for (name, idx) in [
+        ("timestamp", date),
+        ("open", open),
+        ("high", high),
+        ("low", low),
+        ("close", close),
+    ] {
+        if idx.is_none() {
+            missing.push(name);
+        }
+    }
+    if !missing.is_empty() {
+        let unknown = if unknown.is_empty() {
+            "none".to_string()
+        } else {
+            unknown.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        return Err(format!(
+            "missing required columns: {} (unrecognised columns: {})",
+            missing.join(", "),
+            unknown
+        ));
+    }
+
+    let mut extra: Vec<(String, usize)> = unknown
+        .into_iter()
+        .filter_map(|(name, idx)| {
+            let name = name.trim_matches(|c| c == '<' || c == '>').to_lowercase();
+            (!name.is_empty()).then_some((name, idx))
+        })
+        .collect();
+    if let Some(ask) = find(Column::Ask) {
+        extra.push(("ask".to_string(), ask));
+    }
+    if let Some(bid) = bid.filter(|_| find(Column::Close).is_some()) {
+        extra.push(("bid".to_string(), bid));
+    }
+
+    Ok(ColumnMap {
+        date: date.unwrap_or_default(),
+        time,
+        open: open.unwrap_or_default(),
+        high: high.unwrap_or_default(),
+        low: low.unwrap_or_default(),
+        close: close.unwrap_or_default(),
+        tick_volume: find(Column::TickVolume),
+        real_volume: find(Column::Volume),
+        spread: find(Column::Spread),
+        extra,
+        width: fields.len(),
+    })
 }
 
 fn parse_csv_window(path: &Path, offset: usize, limit: usize) -> Result<Vec<Candle>, String> {
-    let (mode, header_present) = detect_mode_and_header(path)?;
+    let (mode, columns) = detect_mode_and_header(path)?;
+    let header_present = columns.is_some();
     let mut candles = Vec::new();
     match mode {
         ParseMode::Csv(delim) => {
@@ -631,7 +1234,7 @@ fn parse_csv_window(path: &Path, offset: usize, limit: usize) -> Result<Vec<Cand
                     break;
                 }
                 let fields: Vec<&str> = record.iter().collect();
-                candles.push(parse_parts(&fields, idx + 1)?);
+                candles.push(parse_parts(&fields, columns.as_ref(), idx + 1)?);
                 seen += 1;
             }
         }
@@ -656,7 +1259,7 @@ fn parse_csv_window(path: &Path, offset: usize, limit: usize) -> Result<Vec<Cand
                     break;
                 }
                 let parts: Vec<&str> = line.split_whitespace().collect();
-                candles.push(parse_parts(&parts, idx + 1)?);
+                candles.push(parse_parts(&parts, columns.as_ref(), idx + 1)?);
                 seen += 1;
             }
         }
@@ -676,7 +1279,10 @@ pub fn load_range_from_path(
     parse_csv_window(&path, offset, limit)
 }
 
-fn parse_parts(parts: &[&str], line_no: usize) -> Result<Candle, String> {
+fn parse_parts(parts: &[&str], columns: Option<&ColumnMap>, line_no: usize) -> Result<Candle, String> {
+    if let Some(columns) = columns {
+        return parse_mapped(parts, columns, line_no);
+    }
     if parts.len() < 5 {
         return Err(format!("invalid column count at line {}", line_no));
     }
@@ -698,6 +1304,13 @@ fn parse_parts(parts: &[&str], line_no: usize) -> Result<Candle, String> {
     } else {
         0.0
     };
+    // headerless MT5 exports: <TICKVOL> <VOL> <SPREAD> follow the prices
+    let (tick_volume, real_volume, spread) = if parts.len() >= start_idx + 7 {
+        let opt = |idx: usize| parse_opt_f64(parts[idx]).map_err(|e| format!("{} at line {}", e, line_no));
+        (Some(volume), opt(start_idx + 5)?, opt(start_idx + 6)?)
+    } else {
+        (None, None, None)
+    };
 
     Ok(Candle {
         ts_utc,
@@ -706,6 +1319,75 @@ fn parse_parts(parts: &[&str], line_no: usize) -> Result<Candle, String> {
         low,
         close,
         volume,
+        tick_volume,
+        real_volume,
+        spread,
+        extra: BTreeMap::new(),
+        spread_avg: None,
+        bar: None,
+        filled: false,
+    })
+}
+
+fn parse_mapped(parts: &[&str], columns: &ColumnMap, line_no: usize) -> Result<Candle, String> {
+    // "timestamp" headers over whitespace-separated rows see the date and
+    // time as two fields, which pushes every later column one to the right
+    let split_ts = columns.time.is_none()
+        && parts.len() > columns.width
+        && parts.len() > columns.date + 1
+        && looks_like_date(parts[columns.date])
+        && looks_like_time(parts[columns.date + 1]);
+    let at = |idx: usize| -> Result<&str, String> {
+        let idx = if split_ts && idx > columns.date { idx + 1 } else { idx };
+        parts
+            .get(idx)
+            .copied()
+            .ok_or_else(|| format!("invalid column count at line {}", line_no))
+    };
+
+    let ts_raw = match columns.time {
+        Some(time) => format!("{} {}", at(columns.date)?.trim(), at(time)?.trim()),
+        None if split_ts => format!("{} {}", parts[columns.date].trim(), parts[columns.date + 1].trim()),
+        None => at(columns.date)?.trim().to_string(),
+    };
+    let ts_utc = normalize_timestamp(&ts_raw).map_err(|e| format!("{} at line {}", e, line_no))?;
+    let open = parse_f64(at(columns.open)?).map_err(|e| format!("{} at line {}", e, line_no))?;
+    let high = parse_f64(at(columns.high)?).map_err(|e| format!("{} at line {}", e, line_no))?;
+    let low = parse_f64(at(columns.low)?).map_err(|e| format!("{} at line {}", e, line_no))?;
+    let close = parse_f64(at(columns.close)?).map_err(|e| format!("{} at line {}", e, line_no))?;
+    let opt = |idx: Option<usize>| -> Result<Option<f64>, String> {
+        match idx {
+            Some(idx) => parse_opt_f64(at(idx)?).map_err(|e| format!("{} at line {}", e, line_no)),
+            None => Ok(None),
+        }
+    };
+    let tick_volume = opt(columns.tick_volume)?;
+    let real_volume = opt(columns.real_volume)?;
+    let spread = opt(columns.spread)?;
+    let volume = tick_volume.or(real_volume).unwrap_or(0.0);
+
+    // extra columns may hold text (symbol, comment); only numbers are kept
+    let mut extra = BTreeMap::new();
+    for (name, idx) in &columns.extra {
+        if let Some(value) = at(*idx).ok().and_then(|v| parse_f64(v).ok()) {
+            extra.insert(name.clone(), value);
+        }
+    }
+
+    Ok(Candle {
+        ts_utc,
+        open,
+        high,
+        low,
+        close,
+        volume,
+        tick_volume,
+        real_volume,
+        spread,
+        extra,
+        spread_avg: None,
+        bar: None,
+        filled: false,
     })
 }
 
@@ -770,6 +1452,13 @@ fn normalize_time(time: &str) -> Result<String, String> {
     Ok(format!("{:02}:{:02}:{:02}", h, m, s))
 }
 
+fn parse_opt_f64(s: &str) -> Result<Option<f64>, String> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_f64(s).map(Some)
+}
+
 fn parse_f64(s: &str) -> Result<f64, String> {
     let cleaned = s.trim().replace(',', "");
     cleaned
@@ -777,68 +1466,101 @@ fn parse_f64(s: &str) -> Result<f64, String> {
         .map_err(|_| format!("invalid number: {}", s))
 }
 
-fn save_to_cache(path: &Path, dataset: &DataSet) -> Result<(), String> {
+/// Logs block-layout timings next to a throwaway row-per-candle write of the
+/// same data.
+fn log_layout_benchmark(app: &AppHandle, cache_path: &Path, dataset: &DataSet, write_ms: u128) {
+    let bytes = fs::metadata(cache_path).map(|m| m.len()).unwrap_or(0);
+    let read_start = std::time::Instant::now();
+    if load_from_cache(app, cache_path).is_err() {
+        return;
+    }
+    let read_ms = read_start.elapsed().as_millis();
+    let _ = logger::log_event(
+        app,
+        &format!("ingest bench blocks write {}ms read {}ms {} bytes", write_ms, read_ms, bytes),
+    );
+    match columnar::bench_row_layout(&cache_path.with_extension("rows.tmp"), &dataset.candles) {
+        Ok(rows) => {
+            let _ = logger::log_event(
+                app,
+                &format!(
+                    "ingest bench rows write {}ms read {}ms {} bytes",
+                    rows.write_ms, rows.read_ms, rows.bytes
+                ),
+            );
+        }
+        Err(err) => {
+            let _ = logger::log_event(app, &format!("ingest bench rows failed {}", err));
+        }
+    }
+}
+
+/// Writes the cache into a temp file and renames it into place, so a crash
+/// mid-write leaves either the old file or none, never a truncated one.
+fn save_to_cache(app: &AppHandle, path: &Path, dataset: &DataSet) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let mut conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS dataset_meta (source_path TEXT);\n         CREATE TABLE IF NOT EXISTS candles (ts_utc TEXT, open REAL, high REAL, low REAL, close REAL, volume REAL);",
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM dataset_meta", []).map_err(|e| e.to_string())?;
-    conn.execute("INSERT INTO dataset_meta (source_path) VALUES (?1)", [&dataset.source_path])
+    let tmp = path.with_extension("sqlite.tmp");
+    if tmp.exists() {
+        fs::remove_file(&tmp).map_err(|e| e.to_string())?;
+    }
+    write_cache_file(&tmp, dataset)?;
+    install_cache_file(app, &tmp, path)
+}
+
+/// Moves a fully written cache file over `path`, dropping pooled connections
+/// to the old one first. The rename replaces the old file in one step, so
+/// readers see either the old cache or the new one, never neither.
+pub fn install_cache_file(app: &AppHandle, tmp: &Path, path: &Path) -> Result<(), String> {
+    app.state::<CachePool>().evict(path);
+    // the old WAL must not be replayed into the new file
+    remove_sidecars(path)?;
+    fs::rename(tmp, path).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Copies the source's cache, WAL included, into a standalone file at `dest`.
+/// Returns `false` when the source has no cache.
+pub fn snapshot_cache(app: &AppHandle, source_path: &str, dest: &Path) -> Result<bool, String> {
+    let cache_path = match cache_path_for_source(app, source_path)? {
+        Some(path) if path.exists() => path,
+        _ => return Ok(false),
+    };
+    if dest.exists() {
+        fs::remove_file(dest).map_err(|e| e.to_string())?;
+    }
+    let conn = cache_conn(app, &cache_path)?;
+    conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()])
         .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM candles", []).map_err(|e| e.to_string())?;
+    Ok(true)
+}
 
+fn write_cache_file(path: &Path, dataset: &DataSet) -> Result<(), String> {
+    let mut conn = schema::open(path)?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-    {
-        let mut stmt = tx
-            .prepare("INSERT INTO candles (ts_utc, open, high, low, close, volume) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
-            .map_err(|e| e.to_string())?;
-        for c in &dataset.candles {
-            stmt.execute((
-                &c.ts_utc,
-                c.open,
-                c.high,
-                c.low,
-                c.close,
-                c.volume,
-            ))
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    tx.execute("INSERT INTO dataset_meta (source_path) VALUES (?1)", [&dataset.source_path])
+        .map_err(|e| e.to_string())?;
+    columnar::write_series(&tx, RAW_SERIES, &dataset.candles)?;
     tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
-fn load_from_cache(path: &Path) -> Result<DataSet, String> {
-    let conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+fn load_from_cache(app: &AppHandle, path: &Path) -> Result<DataSet, String> {
+    let conn = cache_conn(app, path)?;
     let source_path: String = conn
         .query_row("SELECT source_path FROM dataset_meta LIMIT 1", [], |row| row.get(0))
         .map_err(|e| e.to_string())?;
 
-    let mut stmt = conn
-        .prepare("SELECT ts_utc, open, high, low, close, volume FROM candles ORDER BY ROWID ASC")
-        .map_err(|e| e.to_string())?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(Candle {
-                ts_utc: row.get(0)?,
-                open: row.get(1)?,
-                high: row.get(2)?,
-                low: row.get(3)?,
-                close: row.get(4)?,
-                volume: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    let mut candles = Vec::new();
-    for row in rows {
-        candles.push(row.map_err(|e| e.to_string())?);
-    }
+    let mut candles = Vec::with_capacity(columnar::series_len(&conn, RAW_SERIES)?);
+    columnar::blocks_by_index(&conn, RAW_SERIES, 0, usize::MAX, |block| {
+        candles.extend(block.candles);
+        Ok(true)
+    })?;
 
-    Ok(DataSet { source_path, candles })
+    Ok(DataSet {
+        source_path,
+        candles,
+        timeframe: None,
+    })
 }
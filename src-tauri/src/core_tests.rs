@@ -1,6 +1,18 @@
 #[cfg(test)]
 mod tests {
-    use super::super::core::normalize_timestamp;
+    use super::super::columnar::{write_series, BLOCKS_TABLE, BLOCK_SIZE, RAW_SERIES};
+    use super::super::core::{
+        bars_ending_at, load_range_from_path, normalize_query_ts, normalize_timestamp, seek_bar, time_range,
+        Candle, SeekMode, TimeRange,
+    };
+    use super::super::resample::format_ts;
+    use std::path::PathBuf;
+
+    fn write_temp(name: &str, body: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_{}", std::process::id(), name));
+        std::fs::write(&path, body).unwrap();
+        path
+    }
 
     #[test]
     fn normalize_timestamp_works() {
@@ -13,4 +25,202 @@ mod tests {
         let ts = normalize_timestamp("2003.05.05 23:59:59").unwrap();
         assert!(ts.ends_with('Z'));
     }
+
+    #[test]
+    fn query_timestamps_accept_both_styles() {
+        assert_eq!(normalize_query_ts("2015.03.18 14:00:00").unwrap(), "2015-03-18T14:00:00Z");
+        assert_eq!(normalize_query_ts("2015-03-18T14:00:00Z").unwrap(), "2015-03-18T14:00:00Z");
+        assert_eq!(normalize_query_ts("2015-03-18T23:00:00+09:00").unwrap(), "2015-03-18T14:00:00Z");
+        assert!(normalize_query_ts("2015-03-18T14").is_err());
+    }
+
+    #[test]
+    fn header_columns_are_mapped_by_name() {
+        let path = write_temp(
+            "header_mapped.csv",
+            "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>\n\
+             2015.03.18\t14:00:00\t1.1\t1.3\t1.0\t1.2\t42\t0\t3\n",
+        );
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].ts_utc, "2015-03-18T14:00:00Z");
+        assert_eq!(candles[0].high, 1.3);
+        assert_eq!(candles[0].close, 1.2);
+        assert_eq!(candles[0].volume, 42.0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn extended_columns_are_preserved() {
+        let path = write_temp(
+            "header_extended.csv",
+            "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>\t<SWAP>\t<SYMBOL>\n\
+             2015.03.18\t14:00:00\t1.1\t1.3\t1.0\t1.2\t42\t5\t3\t-0.5\tEURUSD\n",
+        );
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles[0].tick_volume, Some(42.0));
+        assert_eq!(candles[0].real_volume, Some(5.0));
+        assert_eq!(candles[0].spread, Some(3.0));
+        assert_eq!(candles[0].extra.get("swap"), Some(&-0.5));
+        assert!(!candles[0].extra.contains_key("symbol"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn headerless_mt5_layout_keeps_spread() {
+        let path = write_temp(
+            "headerless_mt5.csv",
+            "2015.03.18,14:00:00,1.1,1.3,1.0,1.2,42,5,3\n",
+        );
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles[0].volume, 42.0);
+        assert_eq!(candles[0].real_volume, Some(5.0));
+        assert_eq!(candles[0].spread, Some(3.0));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn header_columns_can_be_reordered() {
+        let path = write_temp(
+            "header_reordered.csv",
+            "Close,Volume,Low,High,Open,Timestamp\n1.2,7,1.0,1.3,1.1,2015.03.18 14:00:00\n",
+        );
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles[0].open, 1.1);
+        assert_eq!(candles[0].low, 1.0);
+        assert_eq!(candles[0].volume, 7.0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn header_timestamp_spans_whitespace_fields() {
+        let path = write_temp(
+            "header_whitespace.txt",
+            "timestamp o h l c\n2015.03.18 14:00:00 1.1 1.3 1.0 1.2\n",
+        );
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles[0].ts_utc, "2015-03-18T14:00:00Z");
+        assert_eq!(candles[0].close, 1.2);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn header_without_ohlc_reports_unrecognised_columns() {
+        let path = write_temp(
+            "header_missing.csv",
+            "timestamp,first,max,min,last\n2015.03.18 14:00:00,1.1,1.3,1.0,1.2\n",
+        );
+        let err = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap_err();
+        assert!(err.contains("open, high, low, close"));
+        assert!(err.contains("first, max, min, last"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// Start of the M1 test series, 2015-01-05 00:00 UTC.
+    const BASE: i64 = 1_420_416_000;
+
+    /// Minute of bar `index` in the test series: minutes 100-109 are
+    /// missing, so bar 100 opens at minute 110.
+    fn minute(index: usize) -> i64 {
+        if index < 100 {
+            index as i64
+        } else {
+            index as i64 + 10
+        }
+    }
+
+    fn at(minute: i64) -> String {
+        format_ts(BASE + minute * 60)
+    }
+
+    /// 5000 M1 bars over two blocks, with a gap after bar 99.
+    fn seek_conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(BLOCKS_TABLE).unwrap();
+        let candles: Vec<Candle> = (0..5000)
+            .map(|i| Candle {
+                ts_utc: at(minute(i)),
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        write_series(&conn, RAW_SERIES, &candles).unwrap();
+        conn
+    }
+
+    fn seek(conn: &rusqlite::Connection, ts: &str, mode: SeekMode) -> Option<usize> {
+        seek_bar(conn, None, ts, mode).unwrap().map(|p| p.index)
+    }
+
+    #[test]
+    fn seek_modes_on_exact_matches_gaps_and_ends() {
+        let conn = seek_conn();
+        for mode in [SeekMode::Before, SeekMode::After, SeekMode::Nearest] {
+            assert_eq!(seek(&conn, &at(50), mode), Some(50));
+            assert_eq!(seek(&conn, &at(minute(BLOCK_SIZE)), mode), Some(BLOCK_SIZE));
+        }
+
+        // inside the gap
+        assert_eq!(seek(&conn, &at(105), SeekMode::Before), Some(99));
+        assert_eq!(seek(&conn, &at(105), SeekMode::After), Some(100));
+        assert_eq!(seek(&conn, &at(105), SeekMode::Nearest), Some(100));
+        assert_eq!(seek(&conn, &at(103), SeekMode::Nearest), Some(99));
+
+        // between the last bar of one block and the first of the next
+        let between = format_ts(BASE + minute(BLOCK_SIZE - 1) * 60 + 30);
+        assert_eq!(seek(&conn, &between, SeekMode::Before), Some(BLOCK_SIZE - 1));
+        assert_eq!(seek(&conn, &between, SeekMode::After), Some(BLOCK_SIZE));
+
+        // past either end
+        assert_eq!(seek(&conn, &at(-5), SeekMode::Before), None);
+        assert_eq!(seek(&conn, &at(-5), SeekMode::After), Some(0));
+        assert_eq!(seek(&conn, &at(-5), SeekMode::Nearest), Some(0));
+        assert_eq!(seek(&conn, &at(9000), SeekMode::Before), Some(4999));
+        assert_eq!(seek(&conn, &at(9000), SeekMode::After), None);
+        assert_eq!(seek(&conn, &at(9000), SeekMode::Nearest), Some(4999));
+    }
+
+    #[test]
+    fn bars_ending_at_honours_the_limit() {
+        let conn = seek_conn();
+        let volumes = |range: &TimeRange| {
+            range.candles.iter().map(|c| c.volume as usize).collect::<Vec<_>>()
+        };
+
+        let range = bars_ending_at(&conn, None, &at(minute(BLOCK_SIZE + 4)), 10).unwrap();
+        assert_eq!(range.offset, BLOCK_SIZE - 5);
+        assert_eq!(volumes(&range), (BLOCK_SIZE - 5..=BLOCK_SIZE + 4).collect::<Vec<_>>());
+
+        // fewer bars than asked for
+        let range = bars_ending_at(&conn, None, &at(5), 100).unwrap();
+        assert_eq!((range.offset, range.candles.len()), (0, 6));
+
+        // a timestamp in the gap ends at the bar before it
+        let range = bars_ending_at(&conn, None, &at(105), 3).unwrap();
+        assert_eq!((range.offset, volumes(&range)), (97, vec![97, 98, 99]));
+
+        assert!(bars_ending_at(&conn, None, &at(50), 0).unwrap().candles.is_empty());
+        assert!(bars_ending_at(&conn, None, &at(-5), 10).unwrap().candles.is_empty());
+    }
+
+    #[test]
+    fn time_range_starts_at_the_first_bar_inside() {
+        let conn = seek_conn();
+        let range = time_range(&conn, None, &at(105), &at(112), 100).unwrap();
+        assert_eq!(range.offset, 100);
+        assert_eq!(range.candles.len(), 3);
+
+        let range = time_range(&conn, None, &at(105), &at(112), 2).unwrap();
+        assert_eq!(range.candles.len(), 2);
+
+        let range = time_range(&conn, None, &at(minute(BLOCK_SIZE - 2)), &at(minute(BLOCK_SIZE + 1)), 100).unwrap();
+        assert_eq!((range.offset, range.candles.len()), (BLOCK_SIZE - 2, 4));
+
+        let range = time_range(&conn, None, &at(9000), &at(9100), 100).unwrap();
+        assert_eq!((range.offset, range.candles.len()), (0, 0));
+    }
 }
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::core::{self, Candle};
+use crate::indicators::{self, IndicatorDescriptor};
+use crate::logger;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportLayout {
+    /// `timestamp,open,high,low,close,volume` followed by any indicator columns.
+    #[default]
+    Standard,
+    /// MT5 history layout (`<DATE> <TIME> <OPEN> ... <SPREAD>`), importable by MetaTrader.
+    MetaTrader,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// `2015-03-18T14:00:00Z`
+    #[default]
+    Iso,
+    /// `2015.03.18 14:00:00`
+    MetaTrader,
+    /// Unix seconds.
+    Epoch,
+    /// Unix milliseconds.
+    EpochMillis,
+    /// chrono strftime pattern, e.g. `%Y/%m/%d %H:%M`.
+    Pattern(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportOptions {
+    pub source_path: String,
+    pub dest_path: String,
+    /// Resampled timeframe (`H1`, ...); `None` or `raw` exports the source bars.
+    #[serde(default)]
+    pub timeframe: Option<String>,
+    /// Inclusive bounds of the exported slice, ISO or `YYYY.MM.DD H:MM:SS`.
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Indicators to append, one column per output.
+    #[serde(default)]
+    pub indicators: Vec<IndicatorDescriptor>,
+    #[serde(default)]
+    pub layout: ExportLayout,
+    /// Defaults to tab for `.tsv` files and the MetaTrader layout, comma otherwise.
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    #[serde(default)]
+    pub timestamp_format: TimestampFormat,
+    /// Decimal places for prices and indicator values; shortest form when `None`.
+    #[serde(default)]
+    pub precision: Option<usize>,
+    /// Adds tick_volume, real_volume and spread columns to the standard layout.
+    #[serde(default)]
+    pub include_extended: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportResult {
+    pub path: String,
+    pub rows: usize,
+}
+
+pub fn export_dataset(app: &AppHandle, options: &ExportOptions) -> Result<ExportResult, String> {
+    let start = std::time::Instant::now();
+    let target = core::timeframe_target(options.timeframe.as_deref())?;
+    let target = target.as_deref();
+    core::ensure_cached(app, &options.source_path, target)?;
+    let from = options.from.as_deref().map(core::normalize_query_ts).transpose()?;
+    let to = options.to.as_deref().map(core::normalize_query_ts).transpose()?;
+
+    // written next to the destination and moved over it once complete, so
+    // a failed export never leaves a truncated file behind
+    let tmp = PathBuf::from(format!("{}.tmp", options.dest_path));
+    let rows = match write_export(app, options, target, from.as_deref(), to.as_deref(), &tmp) {
+        Ok(rows) => rows,
+        Err(err) => {
+            let _ = fs::remove_file(&tmp);
+            return Err(err);
+        }
+    };
+    fs::rename(&tmp, &options.dest_path).map_err(|e| e.to_string())?;
+
+    let _ = logger::log_event(
+        app,
+        &format!("export {} rows {}ms", rows, start.elapsed().as_millis()),
+    );
+    Ok(ExportResult {
+        path: options.dest_path.clone(),
+        rows,
+    })
+}
+
+fn write_export(
+    app: &AppHandle,
+    options: &ExportOptions,
+    target: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+    path: &Path,
+) -> Result<usize, String> {
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut writer = ExportWriter::new(BufWriter::new(file), options)?;
+    let cached = if options.indicators.is_empty() {
+        // ts_utc is fixed-width ISO 8601, so these bound every timestamp
+        let (from, to) = (from.unwrap_or(""), to.unwrap_or("~"));
+        core::for_each_cached_candle_between(app, &options.source_path, target, from, to, |_, candle| {
+            writer.write(&candle, &[])
+        })?
+    } else {
+        // indicators need every candle before the first row can be written
+        let mut candles = Vec::new();
+        let cached = core::for_each_cached_candle(app, &options.source_path, target, |_, c| {
+            candles.push(c);
+            Ok(())
+        })?;
+        let series = indicator_series(&options.indicators, &candles)?;
+        let mut values = vec![None; series.len()];
+        for (idx, candle) in candles.iter().enumerate() {
+            if !in_range(&candle.ts_utc, from, to) {
+                continue;
+            }
+            for (slot, s) in values.iter_mut().zip(&series) {
+                *slot = s.get(idx).copied().flatten();
+            }
+            writer.write(candle, &values)?;
+        }
+        cached
+    };
+    if !cached {
+        return Err("source data not cached".to_string());
+    }
+    writer.finish()
+}
+
+/// Every output series of `descriptors` over `candles`, in column order.
+pub fn indicator_series(
+    descriptors: &[IndicatorDescriptor],
+    candles: &[Candle],
+) -> Result<Vec<Vec<Option<f64>>>, String> {
+    let mut series = Vec::new();
+    for desc in descriptors {
+        series.extend(indicators::compute(&desc.normalized()?, candles)?);
+    }
+    Ok(series)
+}
+
+/// Column names for the outputs of `descriptors`: the output name, or the
+/// descriptor ID and output where two indicators share an output name.
+pub fn indicator_columns(descriptors: &[IndicatorDescriptor]) -> Result<Vec<String>, String> {
+    let mut outputs = Vec::new();
+    for desc in descriptors {
+        let desc = desc.normalized()?;
+        let indicator = indicators::find(&desc.kind).ok_or_else(|| format!("unknown indicator: {}", desc.kind))?;
+        outputs.extend(indicator.outputs().iter().map(|output| (desc.id(), *output)));
+    }
+    Ok(outputs
+        .iter()
+        .map(|(id, output)| {
+            if outputs.iter().filter(|(_, other)| other == output).count() > 1 {
+                format!("{}/{}", id, output)
+            } else {
+                output.to_string()
+            }
+        })
+        .collect())
+}
+
+fn in_range(ts_utc: &str, from: Option<&str>, to: Option<&str>) -> bool {
+    // ts_utc is fixed-width ISO 8601, so string order is time order
+    from.is_none_or(|from| ts_utc >= from) && to.is_none_or(|to| ts_utc <= to)
+}
+
+/// Writes export rows one candle at a time.
+pub struct ExportWriter<W: Write> {
+    inner: csv::Writer<W>,
+    layout: ExportLayout,
+    timestamp_format: TimestampFormat,
+    precision: Option<usize>,
+    include_extended: bool,
+    rows: usize,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(out: W, options: &ExportOptions) -> Result<Self, String> {
+        if let TimestampFormat::Pattern(pattern) = &options.timestamp_format {
+            let invalid = chrono::format::StrftimeItems::new(pattern)
+                .any(|item| matches!(item, chrono::format::Item::Error));
+            if invalid {
+                return Err(format!("invalid timestamp pattern: {}", pattern));
+            }
+        }
+        let tsv = Path::new(&options.dest_path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"));
+        let delimiter = match options.delimiter {
+            Some(d) if d.is_ascii() => d as u8,
+            Some(d) => return Err(format!("unsupported delimiter: {}", d)),
+            None if tsv || options.layout == ExportLayout::MetaTrader => b'\t',
+            None => b',',
+        };
+        let mut inner = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(out);
+
+        let mut header: Vec<String> = match options.layout {
+            ExportLayout::Standard => {
+                let mut cols = vec!["timestamp", "open", "high", "low", "close", "volume"];
+                if options.include_extended {
+                    cols.extend(["tick_volume", "real_volume", "spread"]);
+                }
+                cols.into_iter().map(String::from).collect()
+            }
+            ExportLayout::MetaTrader => [
+                "<DATE>", "<TIME>", "<OPEN>", "<HIGH>", "<LOW>", "<CLOSE>", "<TICKVOL>", "<VOL>",
+                "<SPREAD>",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        };
+        header.extend(indicator_columns(&options.indicators)?);
+        inner.write_record(&header).map_err(|e| e.to_string())?;
+
+        Ok(ExportWriter {
+            inner,
+            layout: options.layout,
+            timestamp_format: options.timestamp_format.clone(),
+            precision: options.precision,
+            include_extended: options.include_extended,
+            rows: 0,
+        })
+    }
+
+    pub fn write(&mut self, candle: &Candle, indicators: &[Option<f64>]) -> Result<(), String> {
+        let mut record: Vec<String> = Vec::with_capacity(9 + indicators.len());
+        match self.layout {
+            ExportLayout::Standard => {
+                record.push(format_timestamp(&candle.ts_utc, &self.timestamp_format)?);
+                for price in [candle.open, candle.high, candle.low, candle.close] {
+                    record.push(self.number(price));
+                }
+                record.push(format_plain(candle.volume));
+                if self.include_extended {
+                    for value in [candle.tick_volume, candle.real_volume, candle.spread] {
+                        record.push(value.map(format_plain).unwrap_or_default());
+                    }
+                }
+            }
+            ExportLayout::MetaTrader => {
+                let ts = format_timestamp(&candle.ts_utc, &TimestampFormat::MetaTrader)?;
+                let (date, time) = ts.split_once(' ').unwrap_or((ts.as_str(), ""));
+                record.push(date.to_string());
+                record.push(time.to_string());
+                for price in [candle.open, candle.high, candle.low, candle.close] {
+                    record.push(self.number(price));
+                }
+                let tick_volume = candle.tick_volume.unwrap_or(candle.volume);
+                record.push(format!("{:.0}", tick_volume));
+                record.push(format!("{:.0}", candle.real_volume.unwrap_or(0.0)));
+                record.push(format!("{:.0}", candle.spread.unwrap_or(0.0)));
+            }
+        }
+        for value in indicators {
+            record.push(value.map(|v| self.number(v)).unwrap_or_default());
+        }
+        self.inner.write_record(&record).map_err(|e| e.to_string())?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flushes buffered rows and returns how many were written.
+    pub fn finish(mut self) -> Result<usize, String> {
+        self.inner.flush().map_err(|e| e.to_string())?;
+        Ok(self.rows)
+    }
+
+    fn number(&self, value: f64) -> String {
+        match self.precision {
+            Some(p) => format!("{:.*}", p, value),
+            None => format_plain(value),
+        }
+    }
+}
+
+fn format_plain(value: f64) -> String {
+    format!("{}", value)
+}
+
+pub fn format_timestamp(ts_utc: &str, format: &TimestampFormat) -> Result<String, String> {
+    let parse = || {
+        chrono::DateTime::parse_from_rfc3339(ts_utc)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|_| format!("invalid timestamp: {}", ts_utc))
+    };
+    Ok(match format {
+        TimestampFormat::Iso => ts_utc.to_string(),
+        TimestampFormat::MetaTrader => parse()?.format("%Y.%m.%d %H:%M:%S").to_string(),
+        TimestampFormat::Epoch => parse()?.timestamp().to_string(),
+        TimestampFormat::EpochMillis => parse()?.timestamp_millis().to_string(),
+        TimestampFormat::Pattern(pattern) => parse()?.format(pattern).to_string(),
+    })
+}
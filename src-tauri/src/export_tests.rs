@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use super::super::core::{load_range_from_path, Candle};
+    use super::super::export::{
+        indicator_columns, indicator_series, ExportLayout, ExportOptions, ExportWriter, TimestampFormat,
+    };
+    use super::super::indicators::{IndicatorDescriptor, PriceSource};
+
+    fn options(dest_path: &str) -> ExportOptions {
+        ExportOptions {
+            source_path: String::new(),
+            dest_path: dest_path.to_string(),
+            timeframe: None,
+            from: None,
+            to: None,
+            indicators: vec![],
+            layout: ExportLayout::Standard,
+            delimiter: None,
+            timestamp_format: TimestampFormat::Iso,
+            precision: None,
+            include_extended: false,
+        }
+    }
+
+    fn candle() -> Candle {
+        Candle {
+            ts_utc: "2015-03-18T14:00:00Z".to_string(),
+            open: 1.1,
+            high: 1.3,
+            low: 1.0,
+            close: 1.23456,
+            volume: 42.0,
+            tick_volume: Some(42.0),
+            real_volume: Some(5.0),
+            spread: Some(3.0),
+            ..Default::default()
+        }
+    }
+
+    fn write(options: &ExportOptions, indicators: &[Option<f64>]) -> String {
+        let mut out = Vec::new();
+        let mut writer = ExportWriter::new(&mut out, options).unwrap();
+        writer.write(&candle(), indicators).unwrap();
+        assert_eq!(writer.finish().unwrap(), 1);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn standard_layout_with_indicators_and_precision() {
+        let mut opts = options("out.csv");
+        opts.indicators = vec![IndicatorDescriptor::new("MA", &[]), IndicatorDescriptor::new("rsi", &[])];
+        opts.precision = Some(3);
+        opts.timestamp_format = TimestampFormat::Epoch;
+        let text = write(&opts, &[Some(1.23456), None]);
+        assert_eq!(
+            text,
+            "timestamp,open,high,low,close,volume,ma,rsi\n1426687200,1.100,1.300,1.000,1.235,42,1.235,\n"
+        );
+    }
+
+    #[test]
+    fn indicator_columns_follow_the_registry() {
+        let macd = IndicatorDescriptor::new("macd", &[]);
+        let atr = IndicatorDescriptor::new("atr", &[("period", 2.0)]);
+        assert_eq!(
+            indicator_columns(&[macd.clone(), atr.clone()]).unwrap(),
+            vec!["macd", "signal", "hist", "atr"]
+        );
+        let fast = IndicatorDescriptor::new("ema", &[("period", 2.0)]);
+        let mut high = fast.clone();
+        high.source = PriceSource::High;
+        assert_eq!(
+            indicator_columns(&[fast.clone(), high.clone()]).unwrap(),
+            vec!["ema(period=2)@close/ema", "ema(period=2)@high/ema"]
+        );
+        assert!(indicator_columns(&[IndicatorDescriptor::new("vwap", &[])]).is_err());
+
+        let candles = vec![candle(), candle(), candle()];
+        let series = indicator_series(&[macd, atr, fast, high], &candles).unwrap();
+        assert_eq!(series.len(), 6);
+        assert!((series[3][1].unwrap() - 0.3).abs() < 1e-12);
+        assert_eq!(series[5][2], Some(1.3));
+    }
+
+    #[test]
+    fn tsv_extension_selects_tab_delimiter() {
+        let mut opts = options("out.tsv");
+        opts.timestamp_format = TimestampFormat::Pattern("%Y/%m/%d %H:%M".to_string());
+        let text = write(&opts, &[]);
+        assert!(text.ends_with("2015/03/18 14:00\t1.1\t1.3\t1\t1.23456\t42\n"));
+    }
+
+    #[test]
+    fn invalid_pattern_is_rejected() {
+        let mut opts = options("out.csv");
+        opts.timestamp_format = TimestampFormat::Pattern("%Q".to_string());
+        assert!(ExportWriter::new(Vec::new(), &opts).is_err());
+    }
+
+    #[test]
+    fn metatrader_layout_round_trips_through_parser() {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_export_mt.csv", std::process::id()));
+        let mut opts = options(path.to_str().unwrap());
+        opts.layout = ExportLayout::MetaTrader;
+        std::fs::write(&path, write(&opts, &[])).unwrap();
+        let candles = load_range_from_path(path.to_str().unwrap(), 0, 10).unwrap();
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].ts_utc, "2015-03-18T14:00:00Z");
+        assert_eq!(candles[0].close, 1.23456);
+        assert_eq!(candles[0].tick_volume, Some(42.0));
+        assert_eq!(candles[0].spread, Some(3.0));
+        let _ = std::fs::remove_file(path);
+    }
+}
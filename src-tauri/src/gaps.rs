@@ -0,0 +1,152 @@
+use chrono::{Datelike, TimeZone, Timelike, Weekday};
+use std::collections::BTreeMap;
+use super::core::{Candle, DataSet};
+use super::resample::{self, Interval};
+
+/// What a bar inserted into a gap holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillMode {
+    /// Open, high, low and close at the previous close, no volume.
+    Flat,
+    /// A copy of the previous bar's prices and volumes.
+    Forward,
+    /// NaN prices and volume. They serialize to JSON as `null`.
+    Nan,
+}
+
+/// Periods the market is closed, which are never filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosedHours {
+    /// FX week: Friday 17:00 to Sunday 17:00 New York time.
+    Fx,
+    /// Saturday and Sunday, UTC.
+    Weekend,
+    /// Always open.
+    None,
+}
+
+/// Gap filling options, labelled `<mode>[/<closed hours>]`: `FLAT`, `FFILL`
+/// or `NAN`, then `FX` (the default), `WEEKEND` or `24X7`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapFill {
+    pub mode: FillMode,
+    pub closed: ClosedHours,
+}
+
+impl GapFill {
+    pub fn parse(spec: &str) -> Result<GapFill, String> {
+        let invalid = || format!("invalid gap fill: {}", spec);
+        let upper = spec.trim().to_ascii_uppercase();
+        let (mode, closed) = match upper.split_once('/') {
+            Some((mode, closed)) => (mode, Some(closed)),
+            None => (upper.as_str(), None),
+        };
+        let mode = match mode {
+            "FLAT" => FillMode::Flat,
+            "FFILL" => FillMode::Forward,
+            "NAN" => FillMode::Nan,
+            _ => return Err(invalid()),
+        };
+        let closed = match closed {
+            None | Some("FX") => ClosedHours::Fx,
+            Some("WEEKEND") => ClosedHours::Weekend,
+            Some("24X7") => ClosedHours::None,
+            Some(_) => return Err(invalid()),
+        };
+        Ok(GapFill { mode, closed })
+    }
+
+    pub fn label(&self) -> String {
+        let mode = match self.mode {
+            FillMode::Flat => "FLAT",
+            FillMode::Forward => "FFILL",
+            FillMode::Nan => "NAN",
+        };
+        match self.closed {
+            ClosedHours::Fx => mode.to_string(),
+            ClosedHours::Weekend => format!("{}/WEEKEND", mode),
+            ClosedHours::None => format!("{}/24X7", mode),
+        }
+    }
+}
+
+impl ClosedHours {
+    fn is_closed(self, ts: i64) -> bool {
+        match self {
+            ClosedHours::Fx => {
+                let local = match chrono_tz::America::New_York.timestamp_opt(ts, 0).single() {
+                    Some(local) => local,
+                    None => return false,
+                };
+                match local.weekday() {
+                    Weekday::Sat => true,
+                    Weekday::Fri => local.hour() >= 17,
+                    Weekday::Sun => local.hour() < 17,
+                    _ => false,
+                }
+            }
+            ClosedHours::Weekend => chrono::DateTime::from_timestamp(ts, 0)
+                .is_some_and(|dt| matches!(dt.weekday(), Weekday::Sat | Weekday::Sun)),
+            ClosedHours::None => false,
+        }
+    }
+}
+
+/// Inserts a bar for every missing `interval` step between two bars,
+/// unless the market is closed at its open time. A gap has to hold a whole
+/// bar, so sessions that shrink or grow with DST are not padded.
+pub fn fill_gaps(dataset: &DataSet, interval: Interval, fill: &GapFill) -> Result<DataSet, String> {
+    let step = |ts: i64| match interval {
+        Interval::Fixed(secs) => ts + secs,
+        _ => interval.bucket_end(ts),
+    };
+    let mut out: Vec<Candle> = Vec::with_capacity(dataset.candles.len());
+    for c in &dataset.candles {
+        let ts = resample::parse_ts(&c.ts_utc)?;
+        if let Some(prev) = out.last().cloned() {
+            let mut expected = step(resample::parse_ts(&prev.ts_utc)?);
+            while step(expected) <= ts {
+                if !fill.closed.is_closed(expected) {
+                    out.push(filled_bar(&prev, expected, fill.mode));
+                }
+                expected = step(expected);
+            }
+        }
+        out.push(c.clone());
+    }
+    Ok(DataSet {
+        source_path: dataset.source_path.clone(),
+        candles: out,
+        timeframe: dataset.timeframe.clone(),
+    })
+}
+
+fn filled_bar(prev: &Candle, ts: i64, mode: FillMode) -> Candle {
+    let (price, volume) = match mode {
+        FillMode::Forward => {
+            return Candle {
+                ts_utc: resample::format_ts(ts),
+                bar: None,
+                filled: true,
+                ..prev.clone()
+            }
+        }
+        FillMode::Flat => (prev.close, 0.0),
+        FillMode::Nan => (f64::NAN, f64::NAN),
+    };
+    Candle {
+        ts_utc: resample::format_ts(ts),
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume,
+        tick_volume: prev.tick_volume.map(|_| volume),
+        real_volume: prev.real_volume.map(|_| volume),
+        spread: None,
+        extra: BTreeMap::new(),
+        spread_avg: None,
+        bar: None,
+        filled: true,
+    }
+}
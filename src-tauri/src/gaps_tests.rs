@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use super::super::bars::{build, BarType};
+    use super::super::core::{Candle, DataSet};
+
+    fn dataset(times: &[&str]) -> DataSet {
+        DataSet {
+            source_path: String::new(),
+            candles: times
+                .iter()
+                .enumerate()
+                .map(|(i, ts)| Candle {
+                    ts_utc: ts.to_string(),
+                    open: i as f64,
+                    high: i as f64 + 0.5,
+                    low: i as f64 - 0.5,
+                    close: i as f64 + 0.25,
+                    volume: 10.0,
+                    tick_volume: Some(10.0),
+                    ..Default::default()
+                })
+                .collect(),
+            timeframe: None,
+        }
+    }
+
+    fn times(out: &DataSet) -> Vec<&str> {
+        out.candles.iter().map(|c| &c.ts_utc[11..16]).collect()
+    }
+
+    #[test]
+    fn flat_bars_carry_the_previous_close() {
+        let data = dataset(&["2015-03-18T14:00:00Z", "2015-03-18T14:01:00Z", "2015-03-18T14:04:00Z"]);
+        let out = build(&data, &BarType::parse("fill:flat").unwrap()).unwrap();
+        assert_eq!(out.timeframe.as_deref(), Some("FILL:FLAT"));
+        assert_eq!(times(&out), vec!["14:00", "14:01", "14:02", "14:03", "14:04"]);
+        let filled = &out.candles[2];
+        assert_eq!((filled.open, filled.close, filled.volume), (1.25, 1.25, 0.0));
+        assert_eq!(filled.tick_volume, Some(0.0));
+        assert!(filled.filled);
+        assert!(!out.candles[1].filled);
+
+        let out = build(&data, &BarType::parse("FILL:NAN").unwrap()).unwrap();
+        assert!(out.candles[3].close.is_nan());
+        // NaN goes to the frontend as null and has to come back
+        let json = serde_json::to_string(&out).unwrap();
+        let back: DataSet = serde_json::from_str(&json).unwrap();
+        assert!(back.candles[3].close.is_nan() && back.candles[3].filled);
+        assert_eq!(back.candles[4].close, out.candles[4].close);
+        let out = build(&data, &BarType::parse("FILL:FFILL").unwrap()).unwrap();
+        assert_eq!((out.candles[3].open, out.candles[3].volume), (1.0, 10.0));
+    }
+
+    #[test]
+    fn market_closed_periods_are_not_filled() {
+        // Friday 20:00 UTC to Monday 00:00 UTC, New York closes at 21:00 UTC
+        let data = dataset(&["2015-03-20T19:00:00Z", "2015-03-20T20:00:00Z", "2015-03-23T00:00:00Z"]);
+        let out = build(&data, &BarType::parse("FILL:FLAT:H1").unwrap()).unwrap();
+        let open: Vec<&str> = out.candles.iter().map(|c| c.ts_utc.as_str()).collect();
+        assert_eq!(
+            open,
+            vec![
+                "2015-03-20T19:00:00Z",
+                "2015-03-20T20:00:00Z",
+                "2015-03-22T21:00:00Z",
+                "2015-03-22T22:00:00Z",
+                "2015-03-22T23:00:00Z",
+                "2015-03-23T00:00:00Z",
+            ]
+        );
+        let out = build(&data, &BarType::parse("FILL:FLAT/WEEKEND:H1").unwrap()).unwrap();
+        assert_eq!(out.candles.len(), 6);
+        assert_eq!(out.candles[2].ts_utc, "2015-03-20T21:00:00Z");
+        assert_eq!(build(&data, &BarType::parse("FILL:FLAT/24X7:H1").unwrap()).unwrap().candles.len(), 54);
+    }
+
+    #[test]
+    fn raw_fill_needs_a_timeframe_below_one_minute() {
+        let data = dataset(&["2015-03-18T14:00:00Z", "2015-03-18T14:00:01Z", "2015-03-18T14:00:09Z"]);
+        assert!(build(&data, &BarType::parse("FILL:FLAT").unwrap()).is_err());
+        let out = build(&data, &BarType::parse("FILL:FLAT:M1").unwrap()).unwrap();
+        assert_eq!(out.candles.len(), 1);
+    }
+
+    #[test]
+    fn fill_labels_are_canonical() {
+        assert_eq!(BarType::parse("fill:ffill/fx:h4").unwrap().label(), "FILL:FFILL:H4");
+        assert_eq!(BarType::parse("FILL:NAN/24x7").unwrap().label(), "FILL:NAN/24X7");
+        assert!(BarType::parse("FILL:ZERO:H1").is_err());
+        assert!(BarType::parse("FILL:FLAT:RENKO:10").is_err());
+    }
+}
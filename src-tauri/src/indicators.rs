@@ -1,5 +1,380 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use super::core::Candle;
 
+/// Candle field (or blend of fields) an indicator is computed from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Open,
+    High,
+    Low,
+    #[default]
+    Close,
+    /// (high + low) / 2
+    Median,
+    /// (high + low + close) / 3
+    Typical,
+    /// (high + low + 2 * close) / 4
+    Weighted,
+}
+
+impl PriceSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PriceSource::Open => "open",
+            PriceSource::High => "high",
+            PriceSource::Low => "low",
+            PriceSource::Close => "close",
+            PriceSource::Median => "median",
+            PriceSource::Typical => "typical",
+            PriceSource::Weighted => "weighted",
+        }
+    }
+
+    pub fn price(self, c: &Candle) -> f64 {
+        match self {
+            PriceSource::Open => c.open,
+            PriceSource::High => c.high,
+            PriceSource::Low => c.low,
+            PriceSource::Close => c.close,
+            PriceSource::Median => (c.high + c.low) / 2.0,
+            PriceSource::Typical => (c.high + c.low + c.close) / 3.0,
+            PriceSource::Weighted => (c.high + c.low + 2.0 * c.close) / 4.0,
+        }
+    }
+}
+
+/// An indicator type with its parameters and input price.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndicatorDescriptor {
+    pub kind: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub source: PriceSource,
+}
+
+impl IndicatorDescriptor {
+    pub fn new(kind: &str, params: &[(&str, f64)]) -> IndicatorDescriptor {
+        IndicatorDescriptor {
+            kind: kind.to_string(),
+            params: params.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            source: PriceSource::Close,
+        }
+    }
+
+    /// Stable ID the frontend keys results by, e.g.
+    /// `macd(fast=12,signal=9,slow=26)@close`.
+    pub fn id(&self) -> String {
+        format!("{}({})@{}", self.kind.to_lowercase(), self.params_label(), self.source.as_str())
+    }
+
+    /// Canonical cache key for one output series of this indicator on the
+    /// given timeframe, e.g. `macd:v1(fast=12,signal=9,slow=26)/hist@close;tf=H1`.
+    /// Parameters are sorted by name, so equal descriptors always agree.
+    pub fn cache_key(&self, output: &str, timeframe: Option<&str>) -> String {
+        let kind = self.kind.to_lowercase();
+        format!(
+            "{}:v{}({})/{}@{};tf={}",
+            kind,
+            algorithm_version(&kind),
+            self.params_label(),
+            output,
+            self.source.as_str(),
+            timeframe.unwrap_or("raw")
+        )
+    }
+
+    fn params_label(&self) -> String {
+        self.params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// A copy with unknown kinds and parameters rejected, out of range
+    /// values rejected and missing parameters set to their defaults, so
+    /// equal requests share IDs and cache keys.
+    pub fn normalized(&self) -> Result<IndicatorDescriptor, String> {
+        let indicator = find(&self.kind).ok_or_else(|| format!("unknown indicator: {}", self.kind))?;
+        let schema = indicator.params();
+        if let Some(name) = self.params.keys().find(|name| !schema.iter().any(|p| p.name == name.as_str())) {
+            return Err(format!("{}: unknown parameter {}", indicator.kind(), name));
+        }
+        let mut params = BTreeMap::new();
+        for spec in schema {
+            let value = self.params.get(spec.name).copied().unwrap_or(spec.default);
+            spec.check(indicator.kind(), value)?;
+            params.insert(spec.name.to_string(), value);
+        }
+        Ok(IndicatorDescriptor {
+            kind: indicator.kind().to_string(),
+            params,
+            source: self.source,
+        })
+    }
+}
+
+/// One numeric parameter of an indicator, with its default and valid range.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Whole numbers only, e.g. lengths in bars.
+    pub integer: bool,
+}
+
+impl ParamSpec {
+    const fn period(name: &'static str, default: f64) -> ParamSpec {
+        ParamSpec { name, default, min: 1.0, max: 1000.0, integer: true }
+    }
+
+    fn check(&self, kind: &str, value: f64) -> Result<(), String> {
+        if !(self.min..=self.max).contains(&value) {
+            return Err(format!("{}: {} must be between {} and {}, got {}", kind, self.name, self.min, self.max, value));
+        }
+        if self.integer && value.fract() != 0.0 {
+            return Err(format!("{}: {} must be a whole number, got {}", kind, self.name, value));
+        }
+        Ok(())
+    }
+}
+
+/// Where an indicator's outputs are drawn.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    /// On the price chart, in price units.
+    Overlay,
+    /// In a pane of its own, on its own scale.
+    Pane,
+}
+
+/// Registry entry as the frontend sees it.
+#[derive(Debug, Serialize, Clone)]
+pub struct IndicatorInfo {
+    pub kind: &'static str,
+    pub name: &'static str,
+    pub params: &'static [ParamSpec],
+    pub outputs: &'static [&'static str],
+    pub placement: Placement,
+    /// Leading bars without a value at the default parameters.
+    pub warmup: usize,
+}
+
+/// An indicator kind. Implement this and add the type to `REGISTRY` to make
+/// it available to the cache, the commands and the UI.
+pub trait Indicator: Send + Sync {
+    /// Lowercase identifier used in descriptors and cache keys.
+    fn kind(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn params(&self) -> &'static [ParamSpec];
+    /// Output series, in the order `compute` returns them.
+    fn outputs(&self) -> &'static [&'static str];
+    fn placement(&self) -> Placement;
+    /// Bump whenever the output for the same inputs changes, so series
+    /// cached by the old algorithm stop matching.
+    fn version(&self) -> u32 {
+        1
+    }
+    /// Leading bars without a value.
+    fn warmup(&self, params: &Params) -> usize;
+    fn compute(&self, params: &Params, candles: &[Candle], source: PriceSource) -> Vec<Vec<Option<f64>>>;
+
+    fn info(&self) -> IndicatorInfo {
+        let defaults = Params(self.params().iter().map(|p| (p.name.to_string(), p.default)).collect());
+        IndicatorInfo {
+            kind: self.kind(),
+            name: self.name(),
+            params: self.params(),
+            outputs: self.outputs(),
+            placement: self.placement(),
+            warmup: self.warmup(&defaults),
+        }
+    }
+}
+
+/// Parameter values of a normalized descriptor.
+pub struct Params(BTreeMap<String, f64>);
+
+impl Params {
+    /// The value of `name`; normalization guarantees every parameter in the
+    /// schema is present.
+    pub fn get(&self, name: &str) -> f64 {
+        self.0.get(name).copied().unwrap_or(0.0)
+    }
+
+    pub fn usize(&self, name: &str) -> usize {
+        self.get(name) as usize
+    }
+}
+
+const PERIOD_14: &[ParamSpec] = &[ParamSpec::period("period", 14.0)];
+const MACD_PARAMS: &[ParamSpec] = &[
+    ParamSpec::period("fast", 12.0),
+    ParamSpec::period("slow", 26.0),
+    ParamSpec::period("signal", 9.0),
+];
+
+struct Ma;
+struct Ema;
+struct Rsi;
+struct Macd;
+struct Atr;
+
+impl Indicator for Ma {
+    fn kind(&self) -> &'static str {
+        "ma"
+    }
+    fn name(&self) -> &'static str {
+        "Moving Average"
+    }
+    fn params(&self) -> &'static [ParamSpec] {
+        PERIOD_14
+    }
+    fn outputs(&self) -> &'static [&'static str] {
+        &["ma"]
+    }
+    fn placement(&self) -> Placement {
+        Placement::Overlay
+    }
+    fn warmup(&self, params: &Params) -> usize {
+        params.usize("period") - 1
+    }
+    fn compute(&self, params: &Params, candles: &[Candle], source: PriceSource) -> Vec<Vec<Option<f64>>> {
+        vec![ma(&prices_from_candles(candles, source), params.usize("period"))]
+    }
+}
+
+impl Indicator for Ema {
+    fn kind(&self) -> &'static str {
+        "ema"
+    }
+    fn name(&self) -> &'static str {
+        "Exponential Moving Average"
+    }
+    fn params(&self) -> &'static [ParamSpec] {
+        PERIOD_14
+    }
+    fn outputs(&self) -> &'static [&'static str] {
+        &["ema"]
+    }
+    fn placement(&self) -> Placement {
+        Placement::Overlay
+    }
+    fn warmup(&self, params: &Params) -> usize {
+        params.usize("period") - 1
+    }
+    fn compute(&self, params: &Params, candles: &[Candle], source: PriceSource) -> Vec<Vec<Option<f64>>> {
+        vec![ema(&prices_from_candles(candles, source), params.usize("period"))]
+    }
+}
+
+impl Indicator for Rsi {
+    fn kind(&self) -> &'static str {
+        "rsi"
+    }
+    fn name(&self) -> &'static str {
+        "Relative Strength Index"
+    }
+    fn params(&self) -> &'static [ParamSpec] {
+        PERIOD_14
+    }
+    fn outputs(&self) -> &'static [&'static str] {
+        &["rsi"]
+    }
+    fn placement(&self) -> Placement {
+        Placement::Pane
+    }
+    fn warmup(&self, params: &Params) -> usize {
+        params.usize("period")
+    }
+    fn compute(&self, params: &Params, candles: &[Candle], source: PriceSource) -> Vec<Vec<Option<f64>>> {
+        vec![rsi(&prices_from_candles(candles, source), params.usize("period"))]
+    }
+}
+
+impl Indicator for Macd {
+    fn kind(&self) -> &'static str {
+        "macd"
+    }
+    fn name(&self) -> &'static str {
+        "MACD"
+    }
+    fn params(&self) -> &'static [ParamSpec] {
+        MACD_PARAMS
+    }
+    fn outputs(&self) -> &'static [&'static str] {
+        &["macd", "signal", "hist"]
+    }
+    fn placement(&self) -> Placement {
+        Placement::Pane
+    }
+    fn warmup(&self, params: &Params) -> usize {
+        params.usize("slow") - 1 + params.usize("signal") - 1
+    }
+    fn compute(&self, params: &Params, candles: &[Candle], source: PriceSource) -> Vec<Vec<Option<f64>>> {
+        let (macd_line, signal_line, hist) = macd(
+            &prices_from_candles(candles, source),
+            params.usize("fast"),
+            params.usize("slow"),
+            params.usize("signal"),
+        );
+        vec![macd_line, signal_line, hist]
+    }
+}
+
+impl Indicator for Atr {
+    fn kind(&self) -> &'static str {
+        "atr"
+    }
+    fn name(&self) -> &'static str {
+        "Average True Range"
+    }
+    fn params(&self) -> &'static [ParamSpec] {
+        PERIOD_14
+    }
+    fn outputs(&self) -> &'static [&'static str] {
+        &["atr"]
+    }
+    fn placement(&self) -> Placement {
+        Placement::Pane
+    }
+    fn warmup(&self, params: &Params) -> usize {
+        params.usize("period") - 1
+    }
+    // true range needs the whole bar, so the price source is not used
+    fn compute(&self, params: &Params, candles: &[Candle], _source: PriceSource) -> Vec<Vec<Option<f64>>> {
+        vec![atr(candles, params.usize("period"))]
+    }
+}
+
+/// Every indicator the app offers, in the order the UI lists them.
+pub static REGISTRY: &[&dyn Indicator] = &[&Ma, &Ema, &Rsi, &Macd, &Atr];
+
+pub fn find(kind: &str) -> Option<&'static dyn Indicator> {
+    REGISTRY.iter().copied().find(|i| i.kind().eq_ignore_ascii_case(kind))
+}
+
+pub fn list() -> Vec<IndicatorInfo> {
+    REGISTRY.iter().map(|i| i.info()).collect()
+}
+
+/// Computes every output of `desc` over `candles`.
+pub fn compute(desc: &IndicatorDescriptor, candles: &[Candle]) -> Result<Vec<Vec<Option<f64>>>, String> {
+    let desc = desc.normalized()?;
+    let indicator = find(&desc.kind).ok_or_else(|| format!("unknown indicator: {}", desc.kind))?;
+    Ok(indicator.compute(&Params(desc.params), candles, desc.source))
+}
+
+pub fn algorithm_version(kind: &str) -> u32 {
+    find(kind).map_or(0, |i| i.version())
+}
+
 pub fn ma(values: &[f64], period: usize) -> Vec<Option<f64>> {
     if period == 0 {
         return vec![None; values.len()];
@@ -111,6 +486,28 @@ pub fn macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> (Vec<Opt
     (macd_line, signal_line, hist)
 }
 
-pub fn closes_from_candles(candles: &[Candle]) -> Vec<f64> {
-    candles.iter().map(|c| c.close).collect()
+/// Average true range with Wilder's smoothing.
+pub fn atr(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; candles.len()];
+    if period == 0 || candles.len() < period {
+        return out;
+    }
+    let true_range = |i: usize| {
+        let c = &candles[i];
+        match i.checked_sub(1).map(|p| candles[p].close) {
+            Some(prev) => (c.high - c.low).max((c.high - prev).abs()).max((c.low - prev).abs()),
+            None => c.high - c.low,
+        }
+    };
+    let mut avg = (0..period).map(true_range).sum::<f64>() / period as f64;
+    out[period - 1] = Some(avg);
+    for (i, slot) in out.iter_mut().enumerate().skip(period) {
+        avg = (avg * (period as f64 - 1.0) + true_range(i)) / period as f64;
+        *slot = Some(avg);
+    }
+    out
+}
+
+pub fn prices_from_candles(candles: &[Candle], source: PriceSource) -> Vec<f64> {
+    candles.iter().map(|c| source.price(c)).collect()
 }
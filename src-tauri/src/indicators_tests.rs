@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use super::super::indicators::{ma, rsi, macd};
+    use super::super::core::Candle;
+    use super::super::indicators::{compute, find, list, ma, rsi, macd, IndicatorDescriptor, Placement, PriceSource};
 
     #[test]
     fn ma_basic() {
@@ -25,4 +26,63 @@ mod tests {
         assert_eq!(signal.len(), values.len());
         assert_eq!(hist.len(), values.len());
     }
+
+    #[test]
+    fn cache_key_covers_params_source_and_timeframe() {
+        let a = IndicatorDescriptor::new("macd", &[("slow", 26.0), ("fast", 12.0), ("signal", 9.0)]);
+        let b = IndicatorDescriptor::new("macd", &[("fast", 12.0), ("signal", 9.0), ("slow", 26.0)]);
+        assert_eq!(a.cache_key("hist", None), b.cache_key("hist", None));
+        assert_eq!(a.cache_key("hist", None), "macd:v1(fast=12,signal=9,slow=26)/hist@close;tf=raw");
+
+        let mut c = b.clone();
+        c.params.insert("fast".to_string(), 10.0);
+        assert_ne!(a.cache_key("hist", None), c.cache_key("hist", None));
+        c = b.clone();
+        c.source = PriceSource::Typical;
+        assert_ne!(a.cache_key("hist", None), c.cache_key("hist", None));
+        assert_ne!(a.cache_key("hist", None), a.cache_key("hist", Some("H1")));
+    }
+
+    #[test]
+    fn descriptors_compute_on_their_price_source() {
+        let candles: Vec<Candle> = (0..4)
+            .map(|i| Candle {
+                ts_utc: format!("2015-03-18T14:0{}:00Z", i),
+                open: i as f64,
+                high: i as f64 + 2.0,
+                low: i as f64,
+                close: i as f64 + 1.0,
+                volume: 0.0,
+                ..Default::default()
+            })
+            .collect();
+        let mut desc = IndicatorDescriptor::new("MA", &[("period", 2.0)]);
+        assert_eq!(desc.id(), "ma(period=2)@close");
+        assert_eq!(compute(&desc, &candles).unwrap()[0][1], Some(1.5));
+        desc.source = PriceSource::High;
+        assert_eq!(desc.id(), "ma(period=2)@high");
+        assert_eq!(compute(&desc, &candles).unwrap()[0][1], Some(2.5));
+
+        let macd = IndicatorDescriptor::new("macd", &[]);
+        assert_eq!(compute(&macd, &candles).unwrap().len(), 3);
+        assert!(compute(&IndicatorDescriptor::new("rsi", &[("period", 2.5)]), &candles).is_err());
+        assert!(compute(&IndicatorDescriptor::new("vwap", &[]), &candles).is_err());
+    }
+
+    #[test]
+    fn registry_describes_and_normalizes_indicators() {
+        let kinds: Vec<&str> = list().iter().map(|info| info.kind).collect();
+        assert_eq!(kinds, ["ma", "ema", "rsi", "macd", "atr"]);
+
+        let macd = find("MACD").unwrap().info();
+        assert_eq!(macd.outputs, ["macd", "signal", "hist"]);
+        assert_eq!(macd.placement, Placement::Pane);
+        assert_eq!(macd.warmup, 33);
+        assert_eq!(find("ma").unwrap().placement(), Placement::Overlay);
+
+        let desc = IndicatorDescriptor::new("MACD", &[("fast", 10.0)]).normalized().unwrap();
+        assert_eq!(desc.id(), "macd(fast=10,signal=9,slow=26)@close");
+        assert!(IndicatorDescriptor::new("ma", &[("length", 5.0)]).normalized().is_err());
+        assert!(IndicatorDescriptor::new("ma", &[("period", 0.0)]).normalized().is_err());
+    }
 }
@@ -1,20 +1,39 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod bars;
+mod bars_tests;
+mod bundle;
+mod bundle_tests;
+mod cache;
+mod cache_tests;
+mod charts;
+mod charts_tests;
+mod columnar;
+mod columnar_tests;
 mod core;
 mod core_tests;
+mod export;
+mod export_tests;
+mod gaps;
+mod gaps_tests;
 mod indicators;
 mod indicators_tests;
 mod resample;
+mod resample_tests;
 mod presets;
+mod renko;
+mod renko_tests;
+mod replay;
+mod schema;
+mod schema_tests;
 mod logger;
+mod mtf;
+mod mtf_tests;
+mod pool;
+mod pool_tests;
 
 use tauri::Emitter;
 
-#[allow(dead_code)]
-fn _infer_interval_unused() {
-    // placeholder to keep infer_interval referenced for now
-}
-
 #[tauri::command]
 fn ingest_csv(path: &str, app: tauri::AppHandle) -> Result<core::IngestResult, String> {
     core::load_csv_or_tsv(&app, path)
@@ -30,6 +49,29 @@ fn cache_status(app: tauri::AppHandle) -> Result<core::CacheStatus, String> {
     core::cache_status(&app)
 }
 
+#[tauri::command]
+fn list_cache_entries(app: tauri::AppHandle) -> Result<Vec<cache::CacheEntry>, String> {
+    cache::list_entries(&app)
+}
+
+#[tauri::command]
+fn delete_cache_entry(app: tauri::AppHandle, file: &str) -> Result<bool, String> {
+    cache::delete_entry(&app, file)
+}
+
+#[tauri::command]
+fn load_cache_settings(app: tauri::AppHandle) -> Result<cache::CacheSettings, String> {
+    cache::load_settings(&app)
+}
+
+#[tauri::command]
+fn save_cache_settings(
+    app: tauri::AppHandle,
+    settings: cache::CacheSettings,
+) -> Result<cache::EvictionReport, String> {
+    cache::save_settings(&app, settings)
+}
+
 #[tauri::command]
 fn list_dataset_history(app: tauri::AppHandle) -> Result<Vec<core::DatasetHistory>, String> {
     core::list_dataset_history(&app)
@@ -76,14 +118,15 @@ fn ingest_csv_quick(
         _ => None,
     };
     if let Some(count) = total {
-        let initial = core::load_range_from_cache(&app, &path, 0, initial_limit)?
-            .unwrap_or_default();
-        return Ok(QuickIngestResult {
-            source_path: path,
-            total: count,
-            used_cache: true,
-            initial,
-        });
+        // an unreadable cache is rebuilt by the async ingest, read the file meanwhile
+        if let Ok(Some(initial)) = core::load_range_from_cache(&app, &path, None, 0, initial_limit) {
+            return Ok(QuickIngestResult {
+                source_path: path,
+                total: count,
+                used_cache: true,
+                initial,
+            });
+        }
     }
 
     let initial = core::load_range_from_path(&path, 0, initial_limit)?;
@@ -118,7 +161,7 @@ fn ingest_csv_async(app: tauri::AppHandle, path: String) -> Result<(), String> {
                         total,
                     },
                 );
-                let _ = compute_indicators(app_handle.clone(), result.dataset.clone());
+                let _ = compute_indicators(app_handle.clone(), result.dataset.clone(), None);
                 let _ = app_handle.emit(
                     "ingest_progress",
                     IngestProgress {
@@ -147,102 +190,192 @@ fn ingest_csv_async(app: tauri::AppHandle, path: String) -> Result<(), String> {
 fn dataset_range(
     app: tauri::AppHandle,
     source_path: String,
+    timeframe: Option<String>,
     offset: usize,
     limit: usize,
 ) -> Result<RangeResult, String> {
-    if let Ok(Some(candles)) = core::load_range_from_cache(&app, &source_path, offset, limit) {
+    let target = core::timeframe_target(timeframe.as_deref())?;
+    let target = target.as_deref();
+    if target.is_some() {
+        core::ensure_cached(&app, &source_path, target)?;
+    }
+    if let Ok(Some(candles)) = core::load_range_from_cache(&app, &source_path, target, offset, limit) {
         return Ok(RangeResult { candles });
     }
+    if target.is_some() {
+        return Err("resampled data not cached".to_string());
+    }
     let candles = core::load_range_from_path(&source_path, offset, limit)?;
     Ok(RangeResult { candles })
 }
 
 #[tauri::command]
-fn indicator_range(
+fn dataset_time_range(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    from: String,
+    to: String,
+    limit: Option<usize>,
+) -> Result<Option<core::TimeRange>, String> {
+    let target = core::timeframe_target(timeframe.as_deref())?;
+    core::load_time_range(&app, &source_path, target.as_deref(), &from, &to, limit.unwrap_or(100_000))
+}
+
+#[tauri::command]
+fn dataset_bars_ending_at(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    ts: String,
+    count: usize,
+) -> Result<Option<core::TimeRange>, String> {
+    let target = core::timeframe_target(timeframe.as_deref())?;
+    core::load_bars_ending_at(&app, &source_path, target.as_deref(), &ts, count)
+}
+
+#[tauri::command]
+fn find_bar_index(
     app: tauri::AppHandle,
     source_path: String,
+    timeframe: Option<String>,
+    ts: String,
+    mode: core::SeekMode,
+) -> Result<Option<core::BarPosition>, String> {
+    let target = core::timeframe_target(timeframe.as_deref())?;
+    core::find_bar_index(&app, &source_path, target.as_deref(), &ts, mode)
+}
+
+#[tauri::command]
+fn map_timeframe_bars(
+    app: tauri::AppHandle,
+    source_path: String,
+    lower: Option<String>,
+    higher: String,
     offset: usize,
     limit: usize,
-    indicator: String,
-) -> Result<IndicatorRangeResult, String> {
-    let data = core::load_csv_or_tsv(&app, &source_path)?;
-    let full = compute_indicators(app.clone(), data.dataset)?;
-    let key = indicator.to_lowercase();
-    let series = full
-        .get(&key)
-        .or_else(|| full.get("ma"))
-        .and_then(|v| v.as_array())
-        .ok_or_else(|| "indicator not found".to_string())?;
-    let total = series.len();
-    let start = offset.min(total);
-    let end = (start + limit).min(total);
-    let mut out = Vec::with_capacity(end - start);
-    for value in &series[start..end] {
-        if value.is_null() {
-            out.push(None);
-        } else {
-            out.push(value.as_f64());
-        }
-    }
-    Ok(IndicatorRangeResult { series: out })
+) -> Result<Vec<mtf::BarSpan>, String> {
+    let lower = core::timeframe_target(lower.as_deref())?;
+    let higher = core::timeframe_target(Some(&higher))?
+        .ok_or_else(|| "higher timeframe required".to_string())?;
+    core::bar_spans(&app, &source_path, lower.as_deref(), &higher, offset, limit)
 }
 
 #[tauri::command]
-fn compute_indicators(
+fn containing_bar_index(
     app: tauri::AppHandle,
-    dataset: core::DataSet,
-) -> Result<serde_json::Value, String> {
+    source_path: String,
+    lower: Option<String>,
+    higher: String,
+    lower_index: usize,
+) -> Result<Option<core::BarPosition>, String> {
+    let lower = core::timeframe_target(lower.as_deref())?;
+    let higher = core::timeframe_target(Some(&higher))?
+        .ok_or_else(|| "higher timeframe required".to_string())?;
+    core::containing_bar(&app, &source_path, lower.as_deref(), &higher, lower_index)
+}
+
+#[tauri::command]
+fn indicator_range(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    offset: usize,
+    limit: usize,
+    indicator: indicators::IndicatorDescriptor,
+    output: Option<String>,
+) -> Result<IndicatorRangeResult, String> {
+    let dataset = core::load_dataset(&app, &source_path, timeframe.as_deref())?;
+    let outputs = indicator_outputs(&app, &dataset, &indicator)?;
+    let series = match output {
+        Some(output) => outputs
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&output))
+            .ok_or_else(|| format!("{} has no output {}", indicator.kind, output))?
+            .1,
+        None => outputs
+            .into_iter()
+            .next()
+            .ok_or_else(|| "indicator not found".to_string())?
+            .1,
+    };
+    let start = offset.min(series.len());
+    let end = start.saturating_add(limit).min(series.len());
+    Ok(IndicatorRangeResult {
+        series: series[start..end].to_vec(),
+    })
+}
+
+/// Indicator series keyed by output name.
+type NamedSeries = Vec<(String, Vec<Option<f64>>)>;
+
+/// Every output series of `desc` on `dataset`, served from the indicator
+/// cache when it has them.
+fn indicator_outputs(
+    app: &tauri::AppHandle,
+    dataset: &core::DataSet,
+    desc: &indicators::IndicatorDescriptor,
+) -> Result<NamedSeries, String> {
+    let desc = &desc.normalized()?;
+    let names = indicators::find(&desc.kind)
+        .ok_or_else(|| format!("unknown indicator: {}", desc.kind))?
+        .outputs();
+    // resampled datasets share the source path, so the timeframe keeps their
+    // series apart
+    let tf = dataset.timeframe.as_deref();
+    let keys: Vec<String> = names.iter().map(|name| desc.cache_key(name, tf)).collect();
+
     let use_cache = !dataset.source_path.trim().is_empty();
     if use_cache {
-        if let Ok(Some(cached)) = core::load_indicator_cache(
-            &app,
-            &dataset.source_path,
-            dataset.candles.len(),
-            &["ma", "rsi", "macd", "signal", "hist"],
-        ) {
-            return Ok(cached);
+        if let Ok(Some(cached)) =
+            core::load_indicator_cache(app, &dataset.source_path, dataset.candles.len(), &keys)
+        {
+            return Ok(names.iter().map(|name| name.to_string()).zip(cached).collect());
         }
     }
 
     let start = std::time::Instant::now();
-    let closes = indicators::closes_from_candles(&dataset.candles);
-    let t_closes = start.elapsed().as_millis();
-    let ma = indicators::ma(&closes, 14);
-    let t_ma = start.elapsed().as_millis();
-    let rsi = indicators::rsi(&closes, 14);
-    let t_rsi = start.elapsed().as_millis();
-    let (macd, signal, hist) = indicators::macd(&closes, 12, 26, 9);
-    let t_macd = start.elapsed().as_millis();
-
-    let total = start.elapsed().as_millis();
+    let series = indicators::compute(desc, &dataset.candles)?;
     if cfg!(debug_assertions) {
-        println!(
-            "[perf] indicators closes={}ms ma={}ms rsi={}ms macd={}ms total={}ms",
-            t_closes, t_ma, t_rsi, t_macd, total
-        );
+        println!("[perf] indicator {} total={}ms", desc.id(), start.elapsed().as_millis());
     }
-
     if use_cache {
         let _ = core::save_indicator_cache(
-            &app,
+            app,
             &dataset.source_path,
-            &[
-                ("ma", ma.clone()),
-                ("rsi", rsi.clone()),
-                ("macd", macd.clone()),
-                ("signal", signal.clone()),
-                ("hist", hist.clone()),
-            ],
+            &keys.into_iter().zip(series.iter().cloned()).collect::<Vec<_>>(),
         );
     }
+    Ok(names.iter().map(|name| name.to_string()).zip(series).collect())
+}
 
-    Ok(serde_json::json!({
-        "ma": ma,
-        "rsi": rsi,
-        "macd": macd,
-        "signal": signal,
-        "hist": hist,
-    }))
+#[tauri::command]
+fn list_indicators() -> Vec<indicators::IndicatorInfo> {
+    indicators::list()
+}
+
+#[tauri::command]
+fn compute_indicators(
+    app: tauri::AppHandle,
+    dataset: core::DataSet,
+    indicators: Option<Vec<indicators::IndicatorDescriptor>>,
+) -> Result<serde_json::Value, String> {
+    // results are keyed by descriptor ID, then by output name
+    let requests = indicators.unwrap_or_else(|| {
+        ["ma", "rsi", "macd"]
+            .iter()
+            .map(|kind| indicators::IndicatorDescriptor::new(kind, &[]))
+            .collect()
+    });
+    let mut result = serde_json::Map::new();
+    for desc in &requests {
+        let outputs: serde_json::Map<String, serde_json::Value> = indicator_outputs(&app, &dataset, desc)?
+            .into_iter()
+            .map(|(name, series)| (name, serde_json::json!(series)))
+            .collect();
+        result.insert(desc.normalized()?.id(), serde_json::Value::Object(outputs));
+    }
+    Ok(serde_json::Value::Object(result))
 }
 
 #[tauri::command]
@@ -252,23 +385,24 @@ fn resample_dataset(
     target: String,
 ) -> Result<core::DataSet, String> {
     let start = std::time::Instant::now();
-    let interval = match target.as_str() {
-        "M1" => resample::Interval::M1,
-        "M5" => resample::Interval::M5,
-        "M15" => resample::Interval::M15,
-        "M30" => resample::Interval::M30,
-        "H1" => resample::Interval::H1,
-        "H4" => resample::Interval::H4,
-        "D1" => resample::Interval::D1,
-        _ => return Err("invalid interval".to_string()),
-    };
+    let bar_type = bars::BarType::parse(&target)?;
+    // aliases such as `W1-SUN` share the cache of their canonical label
+    let target = bar_type.label();
 
     if let Ok(Some(cached)) = core::load_resample_cache(&app, &dataset.source_path, &target) {
         return Ok(cached);
     }
 
-    let resampled = resample::resample(&dataset, interval)?;
-    let _ = core::save_resample_cache(&app, &dataset.source_path, &target, &resampled);
+    // an already resampled dataset is rebuilt from the source bars rather
+    // than resampled again
+    let base = match &dataset.timeframe {
+        Some(_) if !dataset.source_path.trim().is_empty() => {
+            core::load_csv_or_tsv(&app, &dataset.source_path)?.dataset
+        }
+        _ => dataset,
+    };
+    let resampled = bars::build(&base, &bar_type)?;
+    let _ = core::save_resample_cache(&app, &base.source_path, &target, &resampled);
     if cfg!(debug_assertions) {
         println!(
             "[perf] resample {} total={}ms",
@@ -279,6 +413,68 @@ fn resample_dataset(
     Ok(resampled)
 }
 
+#[tauri::command]
+fn point_figure_chart(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    options: charts::PointFigureOptions,
+) -> Result<charts::PointFigureChart, String> {
+    let dataset = core::load_dataset(&app, &source_path, timeframe.as_deref())?;
+    charts::point_figure(&dataset.candles, &options)
+}
+
+#[tauri::command]
+fn kagi_chart(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    options: charts::KagiOptions,
+) -> Result<Vec<charts::KagiLine>, String> {
+    let dataset = core::load_dataset(&app, &source_path, timeframe.as_deref())?;
+    charts::kagi(&dataset.candles, &options)
+}
+
+#[tauri::command]
+fn line_break_chart(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframe: Option<String>,
+    options: charts::LineBreakOptions,
+) -> Result<Vec<charts::BreakLine>, String> {
+    let dataset = core::load_dataset(&app, &source_path, timeframe.as_deref())?;
+    charts::line_break(&dataset.candles, &options)
+}
+
+#[tauri::command]
+fn export_dataset(
+    app: tauri::AppHandle,
+    options: export::ExportOptions,
+) -> Result<export::ExportResult, String> {
+    export::export_dataset(&app, &options)
+}
+
+#[tauri::command]
+fn export_bundle(
+    app: tauri::AppHandle,
+    options: bundle::BundleExportOptions,
+) -> Result<bundle::BundleSummary, String> {
+    bundle::export_bundle(&app, &options)
+}
+
+#[tauri::command]
+fn inspect_bundle(path: &str) -> Result<bundle::BundleSummary, String> {
+    bundle::read_summary(std::path::Path::new(path))
+}
+
+#[tauri::command]
+fn import_bundle(
+    app: tauri::AppHandle,
+    options: bundle::BundleImportOptions,
+) -> Result<bundle::BundleImportResult, String> {
+    bundle::import_bundle(&app, &options)
+}
+
 #[tauri::command]
 fn list_presets(app: tauri::AppHandle) -> Result<Vec<presets::Preset>, String> {
     presets::list_presets(&app)
@@ -299,6 +495,26 @@ fn load_preset(app: tauri::AppHandle, name: &str) -> Result<presets::Preset, Str
     presets::load_preset(&app, name)
 }
 
+#[tauri::command]
+fn replay_open(
+    app: tauri::AppHandle,
+    source_path: String,
+    timeframes: Vec<String>,
+    position: usize,
+) -> Result<replay::ReplayFrame, String> {
+    replay::open(&app, &source_path, &timeframes, position)
+}
+
+#[tauri::command]
+fn replay_step(app: tauri::AppHandle, id: u64, count: usize) -> Result<replay::ReplayFrame, String> {
+    replay::step(&app, id, count)
+}
+
+#[tauri::command]
+fn replay_close(app: tauri::AppHandle, id: u64) -> Result<bool, String> {
+    replay::close(&app, id)
+}
+
 #[tauri::command]
 fn save_playback_state(
     app: tauri::AppHandle,
@@ -315,6 +531,8 @@ fn load_playback_state(app: tauri::AppHandle) -> Result<Option<presets::Playback
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .manage(pool::CachePool::default())
+        .manage(replay::ReplaySessions::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
@@ -323,18 +541,38 @@ pub fn run() {
             ingest_csv_async,
             clear_cache,
             cache_status,
+            list_cache_entries,
+            delete_cache_entry,
+            load_cache_settings,
+            save_cache_settings,
             list_dataset_history,
             record_dataset_history,
             dataset_range,
+            dataset_time_range,
+            dataset_bars_ending_at,
+            find_bar_index,
+            map_timeframe_bars,
+            containing_bar_index,
             indicator_range,
+            list_indicators,
             compute_indicators,
             resample_dataset,
+            point_figure_chart,
+            kagi_chart,
+            line_break_chart,
+            export_dataset,
+            export_bundle,
+            inspect_bundle,
+            import_bundle,
             list_presets,
             save_preset,
             delete_preset,
             load_preset,
             save_playback_state,
-            load_playback_state
+            load_playback_state,
+            replay_open,
+            replay_step,
+            replay_close
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,80 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use super::columnar::{self, RAW_SERIES};
+use super::core::{self, BarPosition, Candle};
+
+/// Lower timeframe bars `[lower_start, lower_end)` inside the higher
+/// timeframe bar at `index`; equal bounds when the higher bar has none.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BarSpan {
+    pub index: usize,
+    pub ts_utc: String,
+    pub lower_start: usize,
+    pub lower_end: usize,
+}
+
+fn candles_at(conn: &Connection, series: &str, offset: usize, limit: usize) -> Result<Vec<Candle>, String> {
+    let end = offset.saturating_add(limit);
+    let mut candles = Vec::new();
+    columnar::blocks_by_index(conn, series, offset, end, |block| {
+        let skip = offset.saturating_sub(block.start_idx);
+        let take = end - (block.start_idx + skip);
+        candles.extend(block.candles.into_iter().skip(skip).take(take));
+        Ok(true)
+    })?;
+    Ok(candles)
+}
+
+/// Index of the first `series` bar at or after `ts`, or the series length.
+fn first_at_or_after(conn: &Connection, target: Option<&str>, ts: &str, len: usize) -> Result<usize, String> {
+    Ok(core::seek_one(conn, target, ts, false)?.map_or(len, |p| p.index))
+}
+
+/// Spans of up to `limit` higher timeframe bars from `offset`. A higher bar
+/// owns every lower bar from its open time up to the next higher bar, so
+/// bars merged in from weekend sessions stay with the bar they joined.
+pub fn bar_spans(
+    conn: &Connection,
+    lower: Option<&str>,
+    higher: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<BarSpan>, String> {
+    let lower_len = columnar::series_len(conn, lower.unwrap_or(RAW_SERIES))?;
+    // one extra bar bounds the last span
+    let bars = candles_at(conn, higher, offset, limit.saturating_add(1))?;
+    let mut spans = Vec::with_capacity(bars.len().min(limit));
+    let mut start = match bars.first() {
+        Some(first) => first_at_or_after(conn, lower, &first.ts_utc, lower_len)?,
+        None => return Ok(spans),
+    };
+    for (i, bar) in bars.iter().enumerate().take(limit) {
+        let end = match bars.get(i + 1) {
+            Some(next) => first_at_or_after(conn, lower, &next.ts_utc, lower_len)?,
+            None => lower_len,
+        };
+        spans.push(BarSpan {
+            index: offset + i,
+            ts_utc: bar.ts_utc.clone(),
+            lower_start: start,
+            lower_end: end,
+        });
+        start = end;
+    }
+    Ok(spans)
+}
+
+/// The higher timeframe bar holding the lower timeframe bar at
+/// `lower_index`.
+pub fn containing_bar(
+    conn: &Connection,
+    lower: Option<&str>,
+    higher: &str,
+    lower_index: usize,
+) -> Result<Option<BarPosition>, String> {
+    let bar = match candles_at(conn, lower.unwrap_or(RAW_SERIES), lower_index, 1)?.pop() {
+        Some(bar) => bar,
+        None => return Ok(None),
+    };
+    core::seek_one(conn, Some(higher), &bar.ts_utc, true)
+}
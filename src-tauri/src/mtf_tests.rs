@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use super::super::columnar::{write_series, BLOCKS_TABLE, RAW_SERIES};
+    use super::super::core::Candle;
+    use super::super::mtf::{bar_spans, containing_bar};
+
+    fn candles(times: &[String]) -> Vec<Candle> {
+        times
+            .iter()
+            .map(|ts| Candle {
+                ts_utc: ts.clone(),
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: 1.0,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn conn() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(BLOCKS_TABLE).unwrap();
+        // M5 bars from 13:55 to 15:10 with 14:20-14:30 missing
+        let lower: Vec<String> = (0..16)
+            .map(|i| 13 * 60 + 55 + i * 5)
+            .filter(|m| !(14 * 60 + 20..=14 * 60 + 30).contains(m))
+            .map(|m| format!("2015-03-18T{:02}:{:02}:00Z", m / 60, m % 60))
+            .collect();
+        write_series(&conn, RAW_SERIES, &candles(&lower)).unwrap();
+        let higher: Vec<String> = ["13:00", "14:00", "15:00"]
+            .iter()
+            .map(|t| format!("2015-03-18T{}:00Z", t))
+            .collect();
+        write_series(&conn, "H1", &candles(&higher)).unwrap();
+        conn
+    }
+
+    #[test]
+    fn higher_bars_own_the_lower_bars_until_the_next() {
+        let conn = conn();
+        let spans: Vec<(usize, usize, usize)> = bar_spans(&conn, None, "H1", 0, 10)
+            .unwrap()
+            .iter()
+            .map(|s| (s.index, s.lower_start, s.lower_end))
+            .collect();
+        assert_eq!(spans, vec![(0, 0, 1), (1, 1, 10), (2, 10, 13)]);
+
+        let window = bar_spans(&conn, None, "H1", 1, 1).unwrap();
+        assert_eq!(window.len(), 1);
+        assert_eq!((window[0].lower_start, window[0].lower_end), (1, 10));
+    }
+
+    #[test]
+    fn lower_bars_find_their_higher_bar() {
+        let conn = conn();
+        let bar = containing_bar(&conn, None, "H1", 9).unwrap().unwrap();
+        assert_eq!((bar.index, bar.ts_utc.as_str()), (1, "2015-03-18T14:00:00Z"));
+        assert_eq!(containing_bar(&conn, None, "H1", 10).unwrap().unwrap().index, 2);
+        assert!(containing_bar(&conn, None, "H1", 99).unwrap().is_none());
+    }
+}
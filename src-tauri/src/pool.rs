@@ -0,0 +1,136 @@
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::schema;
+
+/// Idle connections kept per cache file; extra ones are closed on return.
+const MAX_IDLE: usize = 4;
+
+/// Prefix of the error returned for files that fail the integrity check.
+pub const CORRUPT: &str = "cache file is corrupt";
+
+/// Connections to cache files, kept open between commands so scrolling
+/// through `dataset_range` does not reopen the file and re-run schema checks
+/// on every page. Managed as Tauri state.
+#[derive(Default)]
+pub struct CachePool {
+    files: Mutex<HashMap<PathBuf, Arc<FilePool>>>,
+}
+
+#[derive(Default)]
+struct FilePool {
+    idle: Mutex<Vec<Connection>>,
+    /// Set once the file passed its integrity check in this session.
+    checked: AtomicBool,
+}
+
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: Arc<FilePool>,
+}
+
+impl CachePool {
+    pub fn get(&self, path: &Path) -> Result<PooledConnection, String> {
+        let pool = {
+            let mut files = self.files.lock().map_err(|e| e.to_string())?;
+            files.entry(path.to_path_buf()).or_default().clone()
+        };
+        let idle = pool.idle.lock().map_err(|e| e.to_string())?.pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => {
+                if !pool.checked.load(Ordering::Acquire) {
+                    check_integrity(path)?;
+                    pool.checked.store(true, Ordering::Release);
+                }
+                open(path)?
+            }
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool,
+        })
+    }
+
+    /// Closes idle connections to `path`. Call before the file is deleted or
+    /// replaced, otherwise they keep reading the old file.
+    pub fn evict(&self, path: &Path) {
+        if let Ok(mut files) = self.files.lock() {
+            files.remove(path);
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut files) = self.files.lock() {
+            files.clear();
+        }
+    }
+}
+
+pub fn is_corrupt(err: &str) -> bool {
+    err.starts_with(CORRUPT)
+}
+
+/// Runs `PRAGMA quick_check` on an existing file. Truncated or garbled files
+/// either fail to open as a database or report problems here.
+pub fn check_integrity(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let conn = Connection::open(path).map_err(|e| format!("{} ({})", CORRUPT, e))?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("{} ({})", CORRUPT, e))?;
+    if result != "ok" {
+        return Err(format!("{} ({})", CORRUPT, result));
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> Result<Connection, String> {
+    let conn = schema::open(path)?;
+    // WAL lets range reads continue while an ingest or indicator save writes
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;\n\
+         PRAGMA synchronous = NORMAL;\n\
+         PRAGMA temp_store = MEMORY;\n\
+         PRAGMA cache_size = -16000;",
+    )
+    .map_err(|e| e.to_string())?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    conn.set_prepared_statement_cache_capacity(32);
+    Ok(conn)
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection taken")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        // an evicted pool is no longer reachable, so the connection just closes
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            if idle.len() < MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+    }
+}
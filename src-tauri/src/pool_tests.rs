@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use super::super::pool::{is_corrupt, CachePool};
+
+    #[test]
+    fn pooled_connections_use_wal_and_read_during_writes() {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_pool.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = CachePool::default();
+
+        let mut writer = pool.get(&path).unwrap();
+        let mode: String = writer
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        let tx = writer.transaction().unwrap();
+        tx.execute("INSERT INTO dataset_meta (source_path) VALUES ('a.csv')", [])
+            .unwrap();
+        // a second checkout is a separate connection that sees the last commit
+        let reader = pool.get(&path).unwrap();
+        let count: i64 = reader
+            .query_row("SELECT COUNT(*) FROM dataset_meta", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        tx.commit().unwrap();
+        let count: i64 = reader
+            .query_row("SELECT COUNT(*) FROM dataset_meta", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        drop(reader);
+        drop(writer);
+        pool.evict(&path);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn truncated_cache_is_reported_as_corrupt() {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_pool_truncated.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch("CREATE TABLE filler (v BLOB);").unwrap();
+            for _ in 0..64 {
+                conn.execute("INSERT INTO filler VALUES (zeroblob(4096))", []).unwrap();
+            }
+        }
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 3]).unwrap();
+
+        let err = CachePool::default().get(&path).err().unwrap();
+        assert!(is_corrupt(&err), "{}", err);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn garbage_file_is_reported_as_corrupt() {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_pool_garbage.sqlite", std::process::id()));
+        std::fs::write(&path, vec![0x5a; 8192]).unwrap();
+        let err = CachePool::default().get(&path).err().unwrap();
+        assert!(is_corrupt(&err), "{}", err);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,176 @@
+use super::core::{Candle, DataSet};
+use super::indicators;
+use super::resample::{self, Interval};
+
+/// Brick height, either fixed or taken from the market's daily range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrickSize {
+    Pips(f64),
+    /// Latest ATR of this period over daily bars built from the source.
+    Atr(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenkoMode {
+    /// Bricks span exactly their open and close.
+    Traditional,
+    /// Bricks also show the high and low reached while they formed.
+    Wick,
+}
+
+/// Renko bars, labelled `RENKO:<pips>` or `RENKO:ATR<period>`, with a
+/// `:WICK` suffix for wick mode, e.g. `RENKO:10` or `RENKO:ATR14:WICK`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Renko {
+    pub size: BrickSize,
+    pub mode: RenkoMode,
+}
+
+const DAY: i64 = 86400;
+
+impl Renko {
+    pub fn parse(label: &str) -> Result<Renko, String> {
+        let invalid = || format!("invalid renko label: {}", label);
+        let upper = label.trim().to_ascii_uppercase();
+        let mut parts = upper.split(':');
+        if parts.next() != Some("RENKO") {
+            return Err(invalid());
+        }
+        let size = match parts.next() {
+            Some(atr) if atr.starts_with("ATR") => {
+                let period = atr[3..].parse::<usize>().map_err(|_| invalid())?;
+                if period == 0 {
+                    return Err(invalid());
+                }
+                BrickSize::Atr(period)
+            }
+            Some(pips) => {
+                let pips = pips.parse::<f64>().map_err(|_| invalid())?;
+                if !(pips.is_finite() && pips > 0.0) {
+                    return Err(invalid());
+                }
+                BrickSize::Pips(pips)
+            }
+            None => return Err(invalid()),
+        };
+        let mode = match parts.next() {
+            None => RenkoMode::Traditional,
+            Some("WICK") => RenkoMode::Wick,
+            Some(_) => return Err(invalid()),
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Renko { size, mode })
+    }
+
+    pub fn label(&self) -> String {
+        let size = match self.size {
+            BrickSize::Pips(pips) => format!("{}", pips),
+            BrickSize::Atr(period) => format!("ATR{}", period),
+        };
+        match self.mode {
+            RenkoMode::Traditional => format!("RENKO:{}", size),
+            RenkoMode::Wick => format!("RENKO:{}:WICK", size),
+        }
+    }
+}
+
+/// Price of one pip: 0.01 for quotes of 20 and above (JPY crosses,
+/// metals, indices), 0.0001 otherwise.
+pub fn pip_size(price: f64) -> f64 {
+    if price.abs() >= 20.0 {
+        0.01
+    } else {
+        0.0001
+    }
+}
+
+/// Brick height in price units for `dataset`, or `None` if there is not
+/// enough data to size bricks by ATR.
+pub fn brick_height(dataset: &DataSet, size: BrickSize) -> Result<Option<f64>, String> {
+    let first = match dataset.candles.first() {
+        Some(first) => first,
+        None => return Ok(None),
+    };
+    match size {
+        BrickSize::Pips(pips) => Ok(Some(pips * pip_size(first.close))),
+        BrickSize::Atr(period) => {
+            // sources at D1 or above are used as they are
+            let daily = match resample::infer_interval(&dataset.candles).and_then(|i| i.seconds()) {
+                Some(secs) if secs < DAY => resample::resample(dataset, Interval::Fixed(DAY))?.candles,
+                _ => dataset.candles.clone(),
+            };
+            Ok(indicators::atr(&daily, period)
+                .last()
+                .copied()
+                .flatten()
+                .filter(|atr| *atr > 0.0))
+        }
+    }
+}
+
+/// Builds Renko bricks from the closes of `dataset`, which should be the
+/// finest timeframe available. Each brick is stamped with the time of the
+/// bar that completed it, so a fast move gives several bricks on one
+/// timestamp. A reversal needs the price to travel two bricks.
+pub fn renko(dataset: &DataSet, options: &Renko) -> Result<DataSet, String> {
+    let mut out = Vec::new();
+    if let Some(height) = brick_height(dataset, options.size)? {
+        let base = dataset.candles[0].close;
+        // open and close of the last brick, the first one anchored at `base`
+        let (mut bottom, mut top) = (base, base);
+        let mut high = f64::MIN;
+        let mut low = f64::MAX;
+        let mut volume = 0.0;
+        let mut tick_volume = None;
+
+        for c in &dataset.candles {
+            high = high.max(c.high);
+            low = low.min(c.low);
+            volume += c.volume;
+            tick_volume = c.tick_volume.map(|v| v + tick_volume.unwrap_or(0.0)).or(tick_volume);
+            loop {
+                let (open, close) = if c.close >= top + height {
+                    (top, top + height)
+                } else if c.close <= bottom - height {
+                    (bottom, bottom - height)
+                } else {
+                    break;
+                };
+                let (brick_low, brick_high) = (open.min(close), open.max(close));
+                let (brick_low, brick_high) = match options.mode {
+                    RenkoMode::Traditional => (brick_low, brick_high),
+                    RenkoMode::Wick => (brick_low.min(low), brick_high.max(high)),
+                };
+                out.push(Candle {
+                    ts_utc: c.ts_utc.clone(),
+                    open,
+                    high: brick_high,
+                    low: brick_low,
+                    close,
+                    volume,
+                    tick_volume,
+                    real_volume: None,
+                    spread: None,
+                    extra: Default::default(),
+                    spread_avg: None,
+                    bar: None,
+                    filled: false,
+                });
+                bottom = open.min(close);
+                top = open.max(close);
+                // later bricks of the same bar start with no wick
+                high = f64::MIN;
+                low = f64::MAX;
+                volume = 0.0;
+                tick_volume = None;
+            }
+        }
+    }
+    Ok(DataSet {
+        source_path: dataset.source_path.clone(),
+        candles: out,
+        timeframe: Some(options.label()),
+    })
+}
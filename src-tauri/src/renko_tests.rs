@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use super::super::bars::BarType;
+    use super::super::core::{Candle, DataSet};
+    use super::super::renko::{renko, BrickSize, Renko, RenkoMode};
+
+    fn dataset(closes: &[f64]) -> DataSet {
+        DataSet {
+            source_path: String::new(),
+            candles: closes
+                .iter()
+                .enumerate()
+                .map(|(i, close)| Candle {
+                    ts_utc: format!("2015-03-18T14:{:02}:00Z", i),
+                    open: *close,
+                    high: close + 0.0002,
+                    low: close - 0.0002,
+                    close: *close,
+                    volume: 1.0,
+                    ..Default::default()
+                })
+                .collect(),
+            timeframe: None,
+        }
+    }
+
+    fn bricks(out: &DataSet) -> Vec<(String, f64, f64)> {
+        out.candles
+            .iter()
+            .map(|c| (c.ts_utc[14..16].to_string(), (c.open * 1e4).round(), (c.close * 1e4).round()))
+            .collect()
+    }
+
+    #[test]
+    fn bricks_need_two_sizes_to_reverse() {
+        let options = Renko::parse("renko:10").unwrap();
+        let out = renko(&dataset(&[1.1000, 1.1025, 1.1012, 1.0999, 1.0981]), &options).unwrap();
+        assert_eq!(out.timeframe.as_deref(), Some("RENKO:10"));
+        assert_eq!(
+            bricks(&out),
+            vec![
+                ("01".to_string(), 11000.0, 11010.0),
+                ("01".to_string(), 11010.0, 11020.0),
+                ("03".to_string(), 11010.0, 11000.0),
+                ("04".to_string(), 11000.0, 10990.0),
+            ]
+        );
+        assert_eq!(out.candles[0].volume, 2.0);
+        assert_eq!(out.candles[1].volume, 0.0);
+        assert_eq!(out.candles[2].volume, 2.0);
+        assert_eq!(out.candles[2].high, out.candles[2].open);
+    }
+
+    #[test]
+    fn wick_mode_keeps_extremes() {
+        let options = Renko {
+            size: BrickSize::Pips(10.0),
+            mode: RenkoMode::Wick,
+        };
+        let out = renko(&dataset(&[1.1000, 1.0995, 1.1012]), &options).unwrap();
+        assert_eq!(out.candles.len(), 1);
+        assert_eq!((out.candles[0].low * 1e4).round(), 10993.0);
+        assert_eq!((out.candles[0].high * 1e4).round(), 11014.0);
+    }
+
+    #[test]
+    fn renko_labels_are_canonical() {
+        assert_eq!(BarType::parse("renko:atr14:wick").unwrap().label(), "RENKO:ATR14:WICK");
+        assert_eq!(BarType::parse("RENKO:2.5").unwrap().label(), "RENKO:2.5");
+        assert_eq!(BarType::parse("h4").unwrap().label(), "H4");
+        assert!(Renko::parse("RENKO:0").is_err());
+        assert!(Renko::parse("RENKO:ATR0").is_err());
+        assert!(Renko::parse("RENKO:10:BOX").is_err());
+    }
+}
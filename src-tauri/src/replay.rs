@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use crate::core::{self, SeekMode};
+use crate::resample::{self, StreamBars, StreamingResampler, Timeframe};
+
+/// Source bars read from the cache at a time while catching up.
+const FEED_CHUNK: usize = 10_000;
+
+/// Replays kept open at once. A reloaded frontend never closes its old
+/// replays, so opening one more closes the least recently stepped.
+const MAX_REPLAYS: usize = 16;
+
+/// Replays in progress, keyed by id. Managed as Tauri state. Each replay
+/// has its own lock, so one replay reading bars does not hold up the rest.
+#[derive(Default)]
+pub struct ReplaySessions {
+    sessions: Mutex<HashMap<u64, Arc<Replay>>>,
+    next_id: Mutex<u64>,
+}
+
+struct Replay {
+    source_path: String,
+    last_used: Mutex<Instant>,
+    playback: Mutex<Playback>,
+}
+
+struct Playback {
+    position: usize,
+    resampler: StreamingResampler,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayFrame {
+    pub id: u64,
+    /// Source bars played so far; the next step starts at this index.
+    pub position: usize,
+    pub bars: Vec<StreamBars>,
+}
+
+fn source_bars(app: &AppHandle, source_path: &str, offset: usize, limit: usize) -> Result<Vec<core::Candle>, String> {
+    core::load_range_from_cache(app, source_path, None, offset, limit)?
+        .ok_or_else(|| "source data not cached".to_string())
+}
+
+/// Starts replaying `source_path` at `timeframes` with the first `position`
+/// source bars already played, so the current bars come back part built.
+pub fn open(
+    app: &AppHandle,
+    source_path: &str,
+    timeframes: &[String],
+    position: usize,
+) -> Result<ReplayFrame, String> {
+    let targets = timeframes
+        .iter()
+        .map(|tf| Timeframe::parse(tf))
+        .collect::<Result<Vec<_>, _>>()?;
+    core::ensure_cached(app, source_path, None)?;
+    let source = resample::source_interval(&source_bars(app, source_path, 0, 1000)?);
+    let mut resampler = StreamingResampler::new(targets, source)?;
+
+    // catch up from the open of the earliest bar still in progress
+    let mut from = position;
+    if let Some(last) = position.checked_sub(1) {
+        let last = source_bars(app, source_path, last, 1)?;
+        if let Some(last) = last.first() {
+            if let Some(start) = resampler.warmup_start(resample::parse_ts(&last.ts_utc)?) {
+                let start = resample::format_ts(start);
+                from = core::find_bar_index(app, source_path, None, &start, SeekMode::After)?
+                    .map_or(position, |bar| bar.index.min(position));
+            }
+        }
+    }
+    while from < position {
+        let chunk = source_bars(app, source_path, from, FEED_CHUNK.min(position - from))?;
+        if chunk.is_empty() {
+            break;
+        }
+        from += chunk.len();
+        resampler.push(&chunk)?;
+    }
+    let bars = resampler.push(&[])?;
+
+    let state = app.state::<ReplaySessions>();
+    let id = {
+        let mut next_id = state.next_id.lock().map_err(|e| e.to_string())?;
+        *next_id += 1;
+        *next_id
+    };
+    let replay = Replay {
+        source_path: source_path.to_string(),
+        last_used: Mutex::new(Instant::now()),
+        playback: Mutex::new(Playback { position: from, resampler }),
+    };
+    let mut sessions = state.sessions.lock().map_err(|e| e.to_string())?;
+    if sessions.len() >= MAX_REPLAYS {
+        let oldest = sessions
+            .iter()
+            .filter_map(|(id, replay)| replay.last_used.lock().ok().map(|t| (*t, *id)))
+            .min();
+        if let Some((_, oldest)) = oldest {
+            sessions.remove(&oldest);
+        }
+    }
+    sessions.insert(id, Arc::new(replay));
+    Ok(ReplayFrame { id, position: from, bars })
+}
+
+/// Plays the next `count` source bars of replay `id`.
+pub fn step(app: &AppHandle, id: u64, count: usize) -> Result<ReplayFrame, String> {
+    let replay = app
+        .state::<ReplaySessions>()
+        .sessions
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| "replay not found".to_string())?;
+    *replay.last_used.lock().map_err(|e| e.to_string())? = Instant::now();
+    let mut playback = replay.playback.lock().map_err(|e| e.to_string())?;
+    let candles = source_bars(app, &replay.source_path, playback.position, count)?;
+    playback.position += candles.len();
+    let bars = playback.resampler.push(&candles)?;
+    Ok(ReplayFrame {
+        id,
+        position: playback.position,
+        bars,
+    })
+}
+
+pub fn close(app: &AppHandle, id: u64) -> Result<bool, String> {
+    let state = app.state::<ReplaySessions>();
+    let mut sessions = state.sessions.lock().map_err(|e| e.to_string())?;
+    Ok(sessions.remove(&id).is_some())
+}
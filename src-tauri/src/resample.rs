@@ -1,87 +1,542 @@
-use super::core::{Candle, DataSet};
+use chrono::{Datelike, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use super::core::{BarStatus, Candle, DataSet};
 
+/// Bar length of a timeframe. Labels are a unit followed by a count: `S30`,
+/// `M2`, `H12`, `D2`, `W1` (`W1-MON` for Monday weeks) or `MN3`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Interval {
-    M1,
-    M5,
-    M15,
-    M30,
-    H1,
-    H4,
-    D1,
+    /// Fixed number of seconds, aligned to the Unix epoch.
+    Fixed(i64),
+    Weeks(i64, WeekStart),
+    /// Calendar months, starting on the 1st at 00:00 UTC.
+    Months(i64),
 }
 
+/// First day of a weekly bar, at 00:00 UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeekStart {
+    /// FX convention: the Sunday evening open belongs to the coming week.
+    Sunday,
+    Monday,
+}
+
+const DAY: i64 = 86400;
+const WEEK: i64 = 7 * DAY;
+/// Upper bound on the count in a label, well past any useful timeframe.
+const MAX_COUNT: i64 = 100_000;
+/// Candles inspected when inferring a source interval.
+const INFER_SAMPLE: usize = 1000;
+
 impl Interval {
-    pub fn seconds(self) -> i64 {
+    pub const MN1: Interval = Interval::Months(1);
+
+    /// Canonical label, used as the resample cache key; equal intervals
+    /// always get the same label (`M60` and `H1` are both `H1`).
+    pub fn label(self) -> String {
         match self {
-            Interval::M1 => 60,
-            Interval::M5 => 300,
-            Interval::M15 => 900,
-            Interval::M30 => 1800,
-            Interval::H1 => 3600,
-            Interval::H4 => 14400,
-            Interval::D1 => 86400,
+            Interval::Fixed(secs) => {
+                let (unit, size) = [("D", DAY), ("H", 3600), ("M", 60)]
+                    .into_iter()
+                    .find(|(_, size)| secs % size == 0)
+                    .unwrap_or(("S", 1));
+                format!("{}{}", unit, secs / size)
+            }
+            Interval::Weeks(n, WeekStart::Sunday) => format!("W{}", n),
+            Interval::Weeks(n, WeekStart::Monday) => format!("W{}-MON", n),
+            Interval::Months(n) => format!("MN{}", n),
+        }
+    }
+
+    pub fn parse(label: &str) -> Result<Interval, String> {
+        let invalid = || format!("invalid interval: {}", label);
+        let upper = label.trim().to_ascii_uppercase();
+        let (body, week_start) = match upper.split_once('-') {
+            Some((body, "SUN")) => (body, Some(WeekStart::Sunday)),
+            Some((body, "MON")) => (body, Some(WeekStart::Monday)),
+            Some(_) => return Err(invalid()),
+            None => (upper.as_str(), None),
+        };
+        let digits = body.find(|c: char| c.is_ascii_digit()).unwrap_or(body.len());
+        let (unit, count) = body.split_at(digits);
+        // a bare unit (`MN`, `W`) means one of it
+        let count = if count.is_empty() {
+            1
+        } else {
+            count.parse::<i64>().map_err(|_| invalid())?
+        };
+        if !(1..=MAX_COUNT).contains(&count) {
+            return Err(invalid());
+        }
+        let interval = match (unit, week_start) {
+            ("S", None) => Interval::Fixed(count),
+            ("M", None) => Interval::Fixed(count * 60),
+            ("H", None) => Interval::Fixed(count * 3600),
+            ("D", None) => Interval::Fixed(count * DAY),
+            ("W", start) => Interval::Weeks(count, start.unwrap_or(WeekStart::Sunday)),
+            ("MN", None) => Interval::Months(count),
+            _ => return Err(invalid()),
+        };
+        Ok(interval)
+    }
+
+    /// Fixed bar length; `None` for calendar months.
+    pub fn seconds(self) -> Option<i64> {
+        match self {
+            Interval::Fixed(secs) => Some(secs),
+            Interval::Weeks(n, _) => Some(n * WEEK),
+            Interval::Months(_) => None,
+        }
+    }
+
+    /// Open time of the bar containing `ts`.
+    pub fn bucket_start(self, ts: i64) -> i64 {
+        match self {
+            Interval::Fixed(secs) => ts - ts.rem_euclid(secs),
+            // 1970-01-01 was a Thursday
+            Interval::Weeks(n, WeekStart::Sunday) => ts - (ts - 3 * DAY).rem_euclid(n * WEEK),
+            Interval::Weeks(n, WeekStart::Monday) => ts - (ts - 4 * DAY).rem_euclid(n * WEEK),
+            Interval::Months(n) => {
+                let (year, month, _, _, _, _) = from_epoch(ts);
+                let index = year * 12 + month - 1;
+                let start = index - index.rem_euclid(n);
+                to_epoch(start.div_euclid(12), start.rem_euclid(12) + 1, 1, 0, 0, 0)
+            }
+        }
+    }
+
+    /// Close time of the bar opening at `start`.
+    pub fn bucket_end(self, start: i64) -> i64 {
+        match self {
+            Interval::Fixed(secs) => start + secs,
+            Interval::Weeks(n, _) => start + n * WEEK,
+            Interval::Months(n) => {
+                let (year, month, _, _, _, _) = from_epoch(start);
+                let index = year * 12 + month - 1 + n;
+                to_epoch(index.div_euclid(12), index.rem_euclid(12) + 1, 1, 0, 0, 0)
+            }
+        }
+    }
+
+    /// Checks that every bar of `self` is made of whole `source` bars.
+    pub fn check_source(self, source: Interval) -> Result<(), String> {
+        let fits = match (self, source) {
+            (Interval::Months(n), Interval::Months(m)) => n % m == 0,
+            (Interval::Months(_), Interval::Fixed(secs)) => DAY % secs == 0,
+            (Interval::Months(_), Interval::Weeks(..)) => false,
+            (Interval::Weeks(_, a), Interval::Weeks(_, b)) if a != b => false,
+            (target, source) => match (target.seconds(), source.seconds()) {
+                (Some(t), Some(s)) => t % s == 0,
+                _ => false,
+            },
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} is not a multiple of the source interval {}",
+                self.label(),
+                source.label()
+            ))
         }
     }
 }
 
-#[allow(dead_code)]
+/// Detects the bar interval of a series from the smallest gap between its
+/// first candles, so weekend and holiday gaps do not skew it.
 pub fn infer_interval(candles: &[Candle]) -> Option<Interval> {
-    if candles.len() < 2 {
-        return None;
-    }
-    let t0 = parse_ts(&candles[0].ts_utc).ok()?;
-    let t1 = parse_ts(&candles[1].ts_utc).ok()?;
-    let diff = (t1 - t0).abs();
-    match diff {
-        60 => Some(Interval::M1),
-        300 => Some(Interval::M5),
-        900 => Some(Interval::M15),
-        1800 => Some(Interval::M30),
-        3600 => Some(Interval::H1),
-        14400 => Some(Interval::H4),
-        86400 => Some(Interval::D1),
-        _ => None,
+    let times = sample_times(candles);
+    let step = times.windows(2).map(|w| w[1] - w[0]).filter(|d| *d > 0).min()?;
+    if step >= 28 * DAY && times.iter().all(|t| Interval::MN1.bucket_start(*t) == *t) {
+        return Some(Interval::MN1);
+    }
+    if step == WEEK {
+        return [WeekStart::Sunday, WeekStart::Monday]
+            .into_iter()
+            .map(|start| Interval::Weeks(1, start))
+            .find(|w| w.bucket_start(times[0]) == times[0])
+            .or(Some(Interval::Fixed(WEEK)));
     }
+    Some(Interval::Fixed(step))
 }
 
-pub fn resample(dataset: &DataSet, target: Interval) -> Result<DataSet, String> {
-    let mut out = Vec::new();
-    if dataset.candles.is_empty() {
-        return Ok(DataSet {
-            source_path: dataset.source_path.clone(),
-            candles: out,
-        });
+/// The interval of source bars to resample from, or `None` for tick data:
+/// a smallest gap under a minute that other gaps are not multiples of comes
+/// from irregular timestamps, not from a bar interval.
+pub fn source_interval(candles: &[Candle]) -> Option<Interval> {
+    let interval = infer_interval(candles)?;
+    match interval {
+        Interval::Fixed(step) if step < 60 => {
+            let times = sample_times(candles);
+            let regular = times.windows(2).all(|w| (w[1] - w[0]) % step == 0);
+            regular.then_some(interval)
+        }
+        _ => Some(interval),
     }
-    let mut bucket_start = parse_ts(&dataset.candles[0].ts_utc)?;
-    let bucket = target.seconds();
-    bucket_start = bucket_start - (bucket_start % bucket);
+}
 
-    let mut current: Option<Candle> = None;
+fn sample_times(candles: &[Candle]) -> Vec<i64> {
+    candles
+        .iter()
+        .take(INFER_SAMPLE)
+        .filter_map(|c| parse_ts(&c.ts_utc).ok())
+        .collect()
+}
 
-    for c in &dataset.candles {
-        let ts = parse_ts(&c.ts_utc)?;
-        let bucket_time = ts - (ts % bucket);
-        if bucket_time != bucket_start {
-            if let Some(acc) = current.take() {
-                out.push(acc);
+/// An interval plus, optionally, the trading session its bars align to.
+/// Labelled `<interval>@<zone>+<HH:MM>[/merge|/drop]`, e.g.
+/// `D1@America/New_York+17:00/merge`; `@NY` is short for the New York close.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeframe {
+    pub interval: Interval,
+    pub session: Option<Session>,
+}
+
+/// Trading day boundary in local time. Bars of D1 and shorter intervals are
+/// counted from the boundary instead of 00:00 UTC, following DST shifts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub tz: Tz,
+    /// Local time the trading day starts (and the previous one closes).
+    pub day_start: NaiveTime,
+    pub weekend: WeekendMode,
+}
+
+/// Handling of sessions whose trading day falls on a Saturday or Sunday,
+/// typically a few bars between the weekend open and the session boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeekendMode {
+    Keep,
+    /// Saturday bars join Friday's session, Sunday bars Monday's.
+    Merge,
+    Drop,
+}
+
+impl Timeframe {
+    pub fn parse(label: &str) -> Result<Timeframe, String> {
+        let (interval, session) = match label.trim().split_once('@') {
+            Some((interval, session)) => (Interval::parse(interval)?, Some(Session::parse(session)?)),
+            None => (Interval::parse(label)?, None),
+        };
+        if session.is_some() {
+            match interval.seconds() {
+                Some(secs) if secs <= DAY && DAY % secs == 0 => {}
+                _ => return Err("sessions apply to timeframes that divide a day".to_string()),
+            }
+        }
+        Ok(Timeframe { interval, session })
+    }
+
+    pub fn label(&self) -> String {
+        match &self.session {
+            Some(session) => format!("{}@{}", self.interval.label(), session.label()),
+            None => self.interval.label(),
+        }
+    }
+}
+
+impl From<Interval> for Timeframe {
+    fn from(interval: Interval) -> Timeframe {
+        Timeframe {
+            interval,
+            session: None,
+        }
+    }
+}
+
+impl Session {
+    pub fn parse(spec: &str) -> Result<Session, String> {
+        let invalid = || format!("invalid session: {}", spec);
+        let (spec, weekend) = match spec.rsplit_once('/') {
+            Some((rest, mode)) if mode.eq_ignore_ascii_case("merge") => (rest, WeekendMode::Merge),
+            Some((rest, mode)) if mode.eq_ignore_ascii_case("drop") => (rest, WeekendMode::Drop),
+            Some((rest, mode)) if mode.eq_ignore_ascii_case("keep") => (rest, WeekendMode::Keep),
+            _ => (spec, WeekendMode::Keep),
+        };
+        if spec.eq_ignore_ascii_case("NY") {
+            return Ok(Session {
+                tz: chrono_tz::America::New_York,
+                day_start: NaiveTime::from_hms_opt(17, 0, 0).ok_or_else(invalid)?,
+                weekend,
+            });
+        }
+        // zone names such as Etc/GMT+5 contain '+' themselves
+        let (zone, day_start) = match spec.rsplit_once('+') {
+            Some((zone, time)) if time.contains(':') => (
+                zone,
+                NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid())?,
+            ),
+            _ => (spec, NaiveTime::MIN),
+        };
+        let tz = zone.parse::<Tz>().map_err(|_| invalid())?;
+        Ok(Session { tz, day_start, weekend })
+    }
+
+    pub fn label(&self) -> String {
+        let mode = match self.weekend {
+            WeekendMode::Keep => "",
+            WeekendMode::Merge => "/merge",
+            WeekendMode::Drop => "/drop",
+        };
+        format!("{}+{}{}", self.tz.name(), self.day_start.format("%H:%M"), mode)
+    }
+
+    /// UTC bounds `[start, end)` of the trading day containing `ts`.
+    fn bounds(&self, ts: i64) -> (i64, i64) {
+        let local = self.tz.timestamp_opt(ts, 0).single().map(|dt| dt.naive_local());
+        let local = local.unwrap_or_else(|| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default().naive_utc());
+        let mut date = local.date();
+        if local.time() < self.day_start {
+            date = date.pred_opt().unwrap_or(date);
+        }
+        let next = date.succ_opt().unwrap_or(date);
+        (self.to_utc(date), self.to_utc(next))
+    }
+
+    fn to_utc(&self, date: NaiveDate) -> i64 {
+        let local = date.and_time(self.day_start);
+        // a boundary inside a DST gap moves to the first valid instant after it
+        self.tz
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| self.tz.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+            .map(|dt| dt.timestamp())
+            .unwrap_or_else(|| local.and_utc().timestamp())
+    }
+
+    fn trading_weekday(&self, end: i64) -> Weekday {
+        self.tz
+            .timestamp_opt(end - 1, 0)
+            .single()
+            .map(|dt| dt.weekday())
+            .unwrap_or(Weekday::Mon)
+    }
+}
+
+/// Assigns candle timestamps to bar open times, remembering the last
+/// session so consecutive bars skip the time zone lookup.
+struct Bucketer {
+    interval: Interval,
+    session: Option<Session>,
+    current: Option<(i64, i64)>,
+}
+
+impl Bucketer {
+    /// Open and close time of the bar containing `ts`; `None` when it falls
+    /// in a dropped weekend session.
+    fn bucket(&mut self, ts: i64) -> Option<(i64, i64)> {
+        let session = match &self.session {
+            Some(session) => session,
+            None => {
+                let start = self.interval.bucket_start(ts);
+                return Some((start, self.interval.bucket_end(start)));
+            }
+        };
+        let (mut start, mut end) = match self.current {
+            Some((start, end)) if start <= ts && ts < end => (start, end),
+            _ => session.bounds(ts),
+        };
+        self.current = Some((start, end));
+        let weekday = session.trading_weekday(end);
+        if matches!(weekday, Weekday::Sat | Weekday::Sun) {
+            match session.weekend {
+                WeekendMode::Keep => {}
+                WeekendMode::Drop => return None,
+                WeekendMode::Merge if weekday == Weekday::Sat => (start, end) = session.bounds(start - 1),
+                WeekendMode::Merge => (start, end) = session.bounds(end),
             }
-            bucket_start = bucket_time;
         }
-        current = Some(merge_candle(current, c, bucket_start));
+        let secs = self.interval.seconds().unwrap_or(DAY);
+        // bars merged in from a neighbouring session land on its first or last bar
+        let last = (end - start - 1).div_euclid(secs);
+        let open = start + (ts - start).div_euclid(secs).clamp(0, last) * secs;
+        Some((open, (open + secs).min(end)))
     }
+}
 
-    if let Some(acc) = current.take() {
-        out.push(acc);
+pub fn resample(dataset: &DataSet, target: impl Into<Timeframe>) -> Result<DataSet, String> {
+    let target = target.into();
+    let source = source_interval(&dataset.candles);
+    if let Some(source) = source {
+        target.interval.check_source(source)?;
+    }
+    let label = target.label();
+    let mut resampler = Resampler::new(target, source);
+    let mut out = Vec::new();
+    for c in &dataset.candles {
+        out.extend(resampler.push(c)?);
     }
+    out.extend(resampler.current());
 
     Ok(DataSet {
         source_path: dataset.source_path.clone(),
         candles: out,
+        timeframe: Some(label),
     })
 }
 
-fn merge_candle(current: Option<Candle>, incoming: &Candle, bucket_start: i64) -> Candle {
+/// Builds the bars of one timeframe from source bars fed in time order.
+pub struct Resampler {
+    bucketer: Bucketer,
+    /// Source bar length, for expected counts and the completeness check.
+    source: Option<Interval>,
+    bucket: Option<(i64, i64)>,
+    current: Option<Candle>,
+    merged: usize,
+    last_ts: i64,
+}
+
+impl Resampler {
+    pub fn new(target: Timeframe, source: Option<Interval>) -> Resampler {
+        Resampler {
+            bucketer: Bucketer {
+                interval: target.interval,
+                session: target.session,
+                current: None,
+            },
+            source,
+            bucket: None,
+            current: None,
+            merged: 0,
+            last_ts: 0,
+        }
+    }
+
+    /// Adds the next source bar, returning the bar it closed, if any.
+    pub fn push(&mut self, candle: &Candle) -> Result<Option<Candle>, String> {
+        self.last_ts = parse_ts(&candle.ts_utc)?;
+        let (start, end) = match self.bucketer.bucket(self.last_ts) {
+            Some(bounds) => bounds,
+            None => return Ok(None),
+        };
+        let mut closed = None;
+        if self.bucket.map(|(start, _)| start) != Some(start) {
+            if let Some(acc) = self.current.take() {
+                // the source goes on past this bar, so it is complete
+                closed = Some(with_status(acc, self.source, self.bucket, self.merged, true));
+            }
+            self.bucket = Some((start, end));
+            self.merged = 0;
+        }
+        self.merged += 1;
+        self.current = Some(merge_candle(self.current.take(), candle, start, self.merged));
+        Ok(closed)
+    }
+
+    /// The bar being built, as it stands.
+    pub fn current(&self) -> Option<Candle> {
+        let acc = self.current.clone()?;
+        let complete = self.covers_bucket();
+        Some(with_status(acc, self.source, self.bucket, self.merged, complete))
+    }
+
+    /// Open time of the bar `ts` belongs to, without adding anything.
+    pub fn bucket_start(&mut self, ts: i64) -> Option<i64> {
+        let saved = self.bucketer.current;
+        let start = self.bucketer.bucket(ts).map(|(start, _)| start);
+        self.bucketer.current = saved;
+        start
+    }
+
+    /// Whether the last source bar closes with the current bar.
+    fn covers_bucket(&self) -> bool {
+        match (self.source, self.bucket) {
+            (Some(source), Some((_, end))) => {
+                let source_start = source.bucket_start(self.last_ts);
+                self.last_ts + source.bucket_end(source_start) - source_start >= end
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Bars of one timeframe produced by a step of a `StreamingResampler`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamBars {
+    pub timeframe: String,
+    /// Bars closed by the source bars of this step, oldest first.
+    pub completed: Vec<Candle>,
+    /// The bar still being built.
+    pub current: Option<Candle>,
+}
+
+/// Resamples one stream of source bars to several timeframes at once, for
+/// replay where higher timeframe bars build up as the source advances.
+pub struct StreamingResampler {
+    targets: Vec<(String, Resampler)>,
+}
+
+impl StreamingResampler {
+    pub fn new(targets: Vec<Timeframe>, source: Option<Interval>) -> Result<StreamingResampler, String> {
+        let mut resamplers = Vec::with_capacity(targets.len());
+        for target in targets {
+            if let Some(source) = source {
+                target.interval.check_source(source)?;
+            }
+            resamplers.push((target.label(), Resampler::new(target, source)));
+        }
+        Ok(StreamingResampler { targets: resamplers })
+    }
+
+    /// Feeds `candles` in order and reports every timeframe.
+    pub fn push(&mut self, candles: &[Candle]) -> Result<Vec<StreamBars>, String> {
+        let mut out = Vec::with_capacity(self.targets.len());
+        for (label, resampler) in &mut self.targets {
+            let mut completed = Vec::new();
+            for c in candles {
+                completed.extend(resampler.push(c)?);
+            }
+            out.push(StreamBars {
+                timeframe: label.clone(),
+                completed,
+                current: resampler.current(),
+            });
+        }
+        Ok(out)
+    }
+
+    /// Earliest open time among the bars `ts` belongs to, where feeding
+    /// has to begin for every timeframe to show its bar in full.
+    pub fn warmup_start(&mut self, ts: i64) -> Option<i64> {
+        self.targets
+            .iter_mut()
+            .filter_map(|(_, resampler)| resampler.bucket_start(ts))
+            .min()
+    }
+}
+
+fn with_status(
+    mut candle: Candle,
+    source: Option<Interval>,
+    bucket: Option<(i64, i64)>,
+    merged: usize,
+    complete: bool,
+) -> Candle {
+    let expected_bars = match (source, bucket) {
+        (Some(source), Some((start, end))) => {
+            let expected = match (source, source.seconds()) {
+                (_, Some(secs)) => (end - start) as f64 / secs as f64,
+                (Interval::Months(m), None) => {
+                    let (y1, m1, _, _, _, _) = from_epoch(start);
+                    let (y2, m2, _, _, _, _) = from_epoch(end);
+                    ((y2 - y1) * 12 + m2 - m1) as f64 / m as f64
+                }
+                _ => merged as f64,
+            };
+            Some(expected.ceil() as usize)
+        }
+        _ => None,
+    };
+    candle.bar = Some(BarStatus {
+        source_bars: merged,
+        expected_bars,
+        complete,
+    });
+    candle
+}
+
+pub fn merge_candle(current: Option<Candle>, incoming: &Candle, bucket_start: i64, merged: usize) -> Candle {
     match current {
         None => Candle {
             ts_utc: format_ts(bucket_start),
@@ -90,18 +545,46 @@ fn merge_candle(current: Option<Candle>, incoming: &Candle, bucket_start: i64) -
             low: incoming.low,
             close: incoming.close,
             volume: incoming.volume,
+            tick_volume: incoming.tick_volume,
+            real_volume: incoming.real_volume,
+            spread: incoming.spread,
+            extra: incoming.extra.clone(),
+            spread_avg: incoming.spread,
+            bar: None,
+            filled: incoming.filled,
         },
         Some(mut acc) => {
             acc.high = acc.high.max(incoming.high);
             acc.low = acc.low.min(incoming.low);
             acc.close = incoming.close;
             acc.volume += incoming.volume;
+            acc.tick_volume = sum_opt(acc.tick_volume, incoming.tick_volume);
+            acc.real_volume = sum_opt(acc.real_volume, incoming.real_volume);
+            if let Some(spread) = incoming.spread {
+                acc.spread = Some(acc.spread.map_or(spread, |s| s.max(spread)));
+                // running mean; a file carries a spread on every bar or on none
+                let avg = acc.spread_avg.unwrap_or(0.0);
+                acc.spread_avg = Some(avg + (spread - avg) / merged as f64);
+            }
+            // a bar is only a placeholder if everything in it is
+            acc.filled &= incoming.filled;
+            // extra columns have no known aggregation, keep the latest value
+            for (name, value) in &incoming.extra {
+                acc.extra.insert(name.clone(), *value);
+            }
             acc
         }
     }
 }
 
-fn parse_ts(ts: &str) -> Result<i64, String> {
+fn sum_opt(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (None, None) => None,
+        _ => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+    }
+}
+
+pub fn parse_ts(ts: &str) -> Result<i64, String> {
     // expects: YYYY-MM-DDTHH:MM:SSZ
     let ts = ts.trim_end_matches('Z');
     let parts: Vec<&str> = ts.split('T').collect();
@@ -126,7 +609,7 @@ fn parse_ts(ts: &str) -> Result<i64, String> {
     Ok(to_epoch(year, month, day, hour, min, sec))
 }
 
-fn format_ts(epoch: i64) -> String {
+pub fn format_ts(epoch: i64) -> String {
     let (year, month, day, hour, min, sec) = from_epoch(epoch);
     format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, hour, min, sec)
 }
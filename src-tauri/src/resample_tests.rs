@@ -0,0 +1,299 @@
+#[cfg(test)]
+mod tests {
+    use super::super::core::{BarStatus, Candle, DataSet};
+    use super::super::resample::{
+        infer_interval, resample, source_interval, Interval, StreamingResampler, Timeframe,
+        WeekStart,
+    };
+    use std::collections::BTreeMap;
+
+    fn candle(ts: &str, close: f64, tick_volume: f64, spread: f64) -> Candle {
+        Candle {
+            ts_utc: ts.to_string(),
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: tick_volume,
+            tick_volume: Some(tick_volume),
+            spread: Some(spread),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resample_aggregates_extended_fields() {
+        let mut dataset = DataSet {
+            source_path: String::new(),
+            candles: vec![
+                candle("2015-03-18T14:00:00Z", 1.0, 10.0, 2.0),
+                candle("2015-03-18T14:01:00Z", 2.0, 20.0, 6.0),
+                candle("2015-03-18T14:05:00Z", 3.0, 5.0, 1.0),
+            ],
+            timeframe: None,
+        };
+        // source columns that happen to share a name with bar metadata
+        let columns = BTreeMap::from([("complete".to_string(), 7.0), ("spread_avg".to_string(), 9.0)]);
+        dataset.candles[1].extra = columns.clone();
+        let out = resample(&dataset, Interval::Fixed(300)).unwrap();
+        assert_eq!(out.timeframe.as_deref(), Some("M5"));
+        assert_eq!(out.candles.len(), 2);
+        let first = &out.candles[0];
+        assert_eq!(first.ts_utc, "2015-03-18T14:00:00Z");
+        assert_eq!(first.close, 2.0);
+        assert_eq!(first.tick_volume, Some(30.0));
+        assert_eq!(first.real_volume, None);
+        assert_eq!(first.spread, Some(6.0));
+        assert_eq!(first.spread_avg, Some(4.0));
+        assert_eq!(first.extra, columns);
+        assert!(first.bar.unwrap().complete);
+        assert_eq!(out.candles[1].spread, Some(1.0));
+    }
+
+    fn dataset(times: &[&str]) -> DataSet {
+        DataSet {
+            source_path: String::new(),
+            candles: times.iter().map(|ts| candle(ts, 1.0, 1.0, 1.0)).collect(),
+            timeframe: None,
+        }
+    }
+
+    fn opens(out: &DataSet) -> Vec<&str> {
+        out.candles.iter().map(|c| c.ts_utc.as_str()).collect()
+    }
+
+    #[test]
+    fn weeks_start_on_the_configured_day() {
+        // Fri 2015-03-20, Sun 2015-03-22 22:00 (FX open), Mon 2015-03-23
+        let data = dataset(&["2015-03-20T20:00:00Z", "2015-03-22T22:00:00Z", "2015-03-23T01:00:00Z"]);
+        let sunday = resample(&data, Interval::parse("W1").unwrap()).unwrap();
+        assert_eq!(opens(&sunday), vec!["2015-03-15T00:00:00Z", "2015-03-22T00:00:00Z"]);
+        assert_eq!(sunday.candles[1].tick_volume, Some(2.0));
+        assert_eq!(sunday.timeframe.as_deref(), Some("W1"));
+
+        let monday = resample(&data, Interval::Weeks(1, WeekStart::Monday)).unwrap();
+        assert_eq!(opens(&monday), vec!["2015-03-16T00:00:00Z", "2015-03-23T00:00:00Z"]);
+        assert_eq!(monday.timeframe.as_deref(), Some("W1-MON"));
+    }
+
+    #[test]
+    fn months_follow_the_calendar() {
+        let data = dataset(&[
+            "2016-01-31T23:59:00Z",
+            "2016-02-01T00:00:00Z",
+            "2016-02-29T12:00:00Z",
+            "2016-03-01T00:00:00Z",
+        ]);
+        let out = resample(&data, Interval::MN1).unwrap();
+        assert_eq!(
+            opens(&out),
+            vec!["2016-01-01T00:00:00Z", "2016-02-01T00:00:00Z", "2016-03-01T00:00:00Z"]
+        );
+        assert_eq!(out.candles[1].tick_volume, Some(2.0));
+    }
+
+    #[test]
+    fn weekly_and_monthly_spacing_is_inferred() {
+        let weekly = dataset(&["2015-03-16T00:00:00Z", "2015-03-23T00:00:00Z"]);
+        assert_eq!(infer_interval(&weekly.candles), Some(Interval::Weeks(1, WeekStart::Monday)));
+        let weekly = dataset(&["2015-03-15T00:00:00Z", "2015-03-22T00:00:00Z"]);
+        assert_eq!(infer_interval(&weekly.candles), Some(Interval::Weeks(1, WeekStart::Sunday)));
+        let monthly = dataset(&["2015-02-01T00:00:00Z", "2015-03-01T00:00:00Z"]);
+        assert_eq!(infer_interval(&monthly.candles), Some(Interval::MN1));
+        let uneven = dataset(&["2015-02-03T00:00:00Z", "2015-03-03T00:00:00Z"]);
+        assert_eq!(infer_interval(&uneven.candles), Some(Interval::Fixed(28 * 86400)));
+    }
+
+    #[test]
+    fn tick_timestamps_resample_to_minutes_and_hours() {
+        let ticks = dataset(&[
+            "2015-03-18T14:00:03Z",
+            "2015-03-18T14:00:10Z",
+            "2015-03-18T14:00:22Z",
+            "2015-03-18T14:01:05Z",
+            "2015-03-18T15:00:41Z",
+        ]);
+        assert_eq!(infer_interval(&ticks.candles), Some(Interval::Fixed(7)));
+        assert_eq!(source_interval(&ticks.candles), None);
+        let m1 = resample(&ticks, Interval::Fixed(60)).unwrap();
+        assert_eq!(
+            opens(&m1),
+            vec!["2015-03-18T14:00:00Z", "2015-03-18T14:01:00Z", "2015-03-18T15:00:00Z"]
+        );
+        assert_eq!(m1.candles[0].bar.unwrap().source_bars, 3);
+        let h1 = resample(&ticks, Interval::Fixed(3600)).unwrap();
+        assert_eq!(opens(&h1), vec!["2015-03-18T14:00:00Z", "2015-03-18T15:00:00Z"]);
+        let targets = vec![Timeframe::from(Interval::Fixed(3600))];
+        assert!(StreamingResampler::new(targets, source_interval(&ticks.candles)).is_ok());
+
+        // evenly spaced second bars still have to divide the target
+        let seconds =
+            dataset(&["2015-03-18T14:00:00Z", "2015-03-18T14:00:07Z", "2015-03-18T14:00:14Z"]);
+        assert_eq!(source_interval(&seconds.candles), Some(Interval::Fixed(7)));
+        assert!(resample(&seconds, Interval::Fixed(60)).is_err());
+    }
+
+    #[test]
+    fn custom_labels_parse_to_canonical_intervals() {
+        for (label, canonical) in [
+            ("M2", "M2"),
+            ("m10", "M10"),
+            ("M60", "H1"),
+            ("H12", "H12"),
+            ("H24", "D1"),
+            ("D2", "D2"),
+            ("S30", "S30"),
+            ("S90", "S90"),
+            ("W1-SUN", "W1"),
+            ("W2-MON", "W2-MON"),
+            ("MN", "MN1"),
+            ("MN3", "MN3"),
+        ] {
+            assert_eq!(Interval::parse(label).unwrap().label(), canonical, "{}", label);
+        }
+        for label in ["", "M0", "X5", "H1-MON", "M-5", "D1.5"] {
+            assert!(Interval::parse(label).is_err(), "{}", label);
+        }
+    }
+
+    #[test]
+    fn targets_must_be_multiples_of_the_source() {
+        let h1 = dataset(&["2015-03-18T14:00:00Z", "2015-03-18T15:00:00Z", "2015-03-18T17:00:00Z"]);
+        let h3 = resample(&h1, Interval::parse("H3").unwrap()).unwrap();
+        assert_eq!(opens(&h3), vec!["2015-03-18T12:00:00Z", "2015-03-18T15:00:00Z"]);
+        let err = resample(&h1, Interval::parse("M90").unwrap()).unwrap_err();
+        assert_eq!(err, "M90 is not a multiple of the source interval H1");
+        assert!(resample(&h1, Interval::Fixed(1800)).is_err());
+        assert!(resample(&h1, Interval::MN1).is_ok());
+
+        let seconds = dataset(&["2015-03-18T14:00:00Z", "2015-03-18T14:00:10Z", "2015-03-18T14:00:45Z"]);
+        let s30 = resample(&seconds, Interval::parse("S30").unwrap()).unwrap();
+        assert_eq!(opens(&s30), vec!["2015-03-18T14:00:00Z", "2015-03-18T14:00:30Z"]);
+    }
+
+    fn hourly(from: &str, hours: i64) -> DataSet {
+        let start = chrono::DateTime::parse_from_rfc3339(from).unwrap().timestamp();
+        let times: Vec<String> = (0..hours)
+            .map(|h| {
+                chrono::DateTime::from_timestamp(start + h * 3600, 0)
+                    .unwrap()
+                    .format("%Y-%m-%dT%H:%M:%SZ")
+                    .to_string()
+            })
+            .collect();
+        dataset(&times.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn session_labels_are_canonical() {
+        let tf = Timeframe::parse("d1@ny/merge").unwrap();
+        assert_eq!(tf.label(), "D1@America/New_York+17:00/merge");
+        assert_eq!(Timeframe::parse(&tf.label()).unwrap(), tf);
+        assert_eq!(
+            Timeframe::parse("H4@Europe/London+08:30").unwrap().label(),
+            "H4@Europe/London+08:30"
+        );
+        assert_eq!(Timeframe::parse("D1@UTC").unwrap().label(), "D1@UTC+00:00");
+        assert!(Timeframe::parse("W1@NY").is_err());
+        assert!(Timeframe::parse("D1@Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn ny_close_days_follow_dst() {
+        // Thu 2015-03-05 20:00Z .. Tue 2015-03-10; US DST starts Sun 2015-03-08
+        let data = hourly("2015-03-05T20:00:00Z", 24 * 5);
+        let keep = resample(&data, Timeframe::parse("D1@NY").unwrap()).unwrap();
+        assert_eq!(
+            opens(&keep),
+            vec![
+                "2015-03-04T22:00:00Z",
+                "2015-03-05T22:00:00Z",
+                // Friday 17:00 EST onwards is Saturday's stub session
+                "2015-03-06T22:00:00Z",
+                "2015-03-07T22:00:00Z",
+                // 17:00 EDT after the switch
+                "2015-03-08T21:00:00Z",
+                "2015-03-09T21:00:00Z",
+            ]
+        );
+        assert_eq!(keep.timeframe.as_deref(), Some("D1@America/New_York+17:00"));
+
+        let merged = resample(&data, Timeframe::parse("D1@NY/merge").unwrap()).unwrap();
+        assert_eq!(
+            opens(&merged),
+            vec!["2015-03-04T22:00:00Z", "2015-03-05T22:00:00Z", "2015-03-08T21:00:00Z", "2015-03-09T21:00:00Z"]
+        );
+        // Friday's session absorbs Saturday's bars, Monday's Sunday's
+        assert_eq!(merged.candles[1].tick_volume, Some(48.0));
+        assert_eq!(merged.candles[2].tick_volume, Some(47.0));
+
+        let dropped = resample(&data, Timeframe::parse("D1@NY/drop").unwrap()).unwrap();
+        assert_eq!(opens(&dropped), opens(&merged));
+        assert_eq!(dropped.candles[1].tick_volume, Some(24.0));
+    }
+
+    #[test]
+    fn h4_bars_count_from_the_session_start() {
+        let data = hourly("2015-06-15T21:00:00Z", 9);
+        let out = resample(&data, Timeframe::parse("H4@NY").unwrap()).unwrap();
+        assert_eq!(
+            opens(&out),
+            vec!["2015-06-15T21:00:00Z", "2015-06-16T01:00:00Z", "2015-06-16T05:00:00Z"]
+        );
+    }
+
+    #[test]
+    fn trailing_partial_bar_is_flagged() {
+        let fill = |c: &Candle| {
+            let bar = c.bar.unwrap();
+            (bar.source_bars, bar.expected_bars.unwrap(), bar.complete)
+        };
+        let times: Vec<String> = (0..7).map(|m| format!("2015-03-18T14:{:02}:00Z", m)).collect();
+        let times: Vec<&str> = times.iter().map(String::as_str).collect();
+        let out = resample(&dataset(&times), Interval::Fixed(300)).unwrap();
+        assert_eq!(fill(&out.candles[0]), (5, 5, true));
+        assert_eq!(fill(&out.candles[1]), (2, 5, false));
+
+        let out = resample(&dataset(&times[..5]), Interval::Fixed(300)).unwrap();
+        assert_eq!(fill(&out.candles[0]), (5, 5, true));
+
+        // gaps inside a bar leave it complete with fewer source bars
+        let out = resample(&dataset(&[times[0], times[2], times[4], times[5]]), Interval::Fixed(300)).unwrap();
+        assert_eq!(fill(&out.candles[0]), (3, 5, true));
+    }
+
+    #[test]
+    fn streaming_bars_build_up_and_match_the_batch() {
+        let times: Vec<String> = (0..12).map(|m| format!("2015-03-18T14:{:02}:00Z", m)).collect();
+        let times: Vec<&str> = times.iter().map(String::as_str).collect();
+        let data = dataset(&times);
+        let targets = vec![Timeframe::parse("M5").unwrap(), Timeframe::parse("M10").unwrap()];
+        let mut stream = StreamingResampler::new(targets, Some(Interval::Fixed(60))).unwrap();
+
+        let step = stream.push(&data.candles[..3]).unwrap();
+        assert_eq!(step[0].timeframe, "M5");
+        assert!(step[0].completed.is_empty());
+        let current = step[0].current.as_ref().unwrap();
+        assert_eq!(
+            current.bar,
+            Some(BarStatus {
+                source_bars: 3,
+                expected_bars: Some(5),
+                complete: false,
+            })
+        );
+
+        let step = stream.push(&data.candles[3..5]).unwrap();
+        // the fifth minute closes the bar, but only the next one reports it
+        assert!(step[0].current.as_ref().unwrap().bar.unwrap().complete);
+        let step = stream.push(&data.candles[5..]).unwrap();
+        assert_eq!(step[0].completed.len(), 2);
+        assert_eq!(step[1].completed.len(), 1);
+        assert_eq!(step[1].current.as_ref().unwrap().ts_utc, "2015-03-18T14:10:00Z");
+
+        let batch = resample(&data, Interval::Fixed(300)).unwrap();
+        assert_eq!(batch.candles[1].ts_utc, step[0].completed[1].ts_utc);
+        assert_eq!(batch.candles[1].extra, step[0].completed[1].extra);
+        assert_eq!(stream.warmup_start(1426688100), Some(1426687800));
+    }
+}
@@ -0,0 +1,208 @@
+use rusqlite::Connection;
+use std::path::Path;
+use super::columnar;
+use super::core::Candle;
+
+/// Bump when the cache layout changes and add the matching step to `migrate`.
+pub const SCHEMA_VERSION: i32 = 6;
+
+const TABLES: &[&str] = &[
+    "dataset_meta",
+    "candle_blocks",
+    "resample_meta",
+    "indicator_meta",
+];
+
+/// Row-per-value tables replaced by `candle_blocks` and `indicator_meta.data`
+/// in version 3.
+const ROW_TABLES: &[&str] = &["candles", "resample_candles", "indicator_values"];
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS dataset_meta (source_path TEXT);\n\
+     CREATE TABLE IF NOT EXISTS resample_meta (target TEXT PRIMARY KEY, count INTEGER);\n\
+     CREATE TABLE IF NOT EXISTS indicator_meta (\n\
+       indicator TEXT PRIMARY KEY,\n\
+       count INTEGER,\n\
+       data BLOB,\n\
+       last_used INTEGER\n\
+     );";
+
+/// Columns added to the candle tables after the first release, which wrote
+/// unstamped files.
+const EXTENDED_CANDLE_COLUMNS: &[(&str, &str)] = &[
+    ("tick_volume", "REAL"),
+    ("real_volume", "REAL"),
+    ("spread", "REAL"),
+    ("extra", "TEXT"),
+];
+
+/// Opens a cache file, bringing its schema up to `SCHEMA_VERSION`.
+///
+/// Older files are migrated in place. Files stamped by a newer build cannot
+/// be interpreted, so their tables are dropped and the cache reads as empty
+/// until it is written again.
+pub fn open(path: &Path) -> Result<Connection, String> {
+    let mut conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let version = user_version(&conn)?;
+    if version == SCHEMA_VERSION {
+        return Ok(conn);
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if version > SCHEMA_VERSION {
+        for table in TABLES.iter().chain(ROW_TABLES) {
+            tx.execute_batch(&format!("DROP TABLE IF EXISTS {};", table))
+                .map_err(|e| e.to_string())?;
+        }
+    } else {
+        migrate(&tx, version)?;
+    }
+    tx.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+    tx.execute_batch(columnar::BLOCKS_TABLE).map_err(|e| e.to_string())?;
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+pub fn user_version(conn: &Connection) -> Result<i32, String> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn migrate(conn: &Connection, from: i32) -> Result<(), String> {
+    if from < 1 {
+        // unstamped files predate or include the extended candle columns
+        for table in ["candles", "resample_candles"] {
+            add_missing_columns(conn, table, EXTENDED_CANDLE_COLUMNS)?;
+        }
+    }
+    // 2: timestamp indexes on the row tables, superseded by 3
+    if from < 3 {
+        convert_rows_to_blocks(conn)?;
+    }
+    if from < 4 && table_exists(conn, "indicator_meta")? {
+        // entries were keyed by bare names ("ma", "rsi"), which say nothing
+        // about the parameters they were computed with
+        add_missing_columns(conn, "indicator_meta", &[("last_used", "INTEGER")])?;
+        conn.execute_batch("DELETE FROM indicator_meta;")
+            .map_err(|e| e.to_string())?;
+    }
+    // 5: resampled bars gained fill counts and a completeness flag
+    // 6: which moved from `extra` into columns of their own
+    if from < 6 && table_exists(conn, "resample_meta")? {
+        // resampled bars are rebuilt from the source bars on the next request
+        conn.execute_batch("DELETE FROM resample_meta;")
+            .map_err(|e| e.to_string())?;
+        if table_exists(conn, "candle_blocks")? {
+            conn.execute_batch("DELETE FROM candle_blocks WHERE series <> '';")
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Re-encodes the row-per-candle and row-per-value tables into blocks and
+/// drops them.
+fn convert_rows_to_blocks(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(columnar::BLOCKS_TABLE).map_err(|e| e.to_string())?;
+    if table_exists(conn, "candles")? {
+        let candles = read_rows(conn, "SELECT {} FROM candles ORDER BY ROWID ASC", None)?;
+        columnar::write_series(conn, columnar::RAW_SERIES, &candles)?;
+    }
+    if table_exists(conn, "resample_candles")? {
+        for target in distinct(conn, "SELECT DISTINCT target FROM resample_candles")? {
+            let candles = read_rows(
+                conn,
+                "SELECT {} FROM resample_candles WHERE target = ?1 ORDER BY idx ASC",
+                Some(&target),
+            )?;
+            columnar::write_series(conn, &target, &candles)?;
+        }
+    }
+    if table_exists(conn, "indicator_meta")? {
+        add_missing_columns(conn, "indicator_meta", &[("data", "BLOB")])?;
+        if table_exists(conn, "indicator_values")? {
+            for name in distinct(conn, "SELECT indicator FROM indicator_meta")? {
+                let count: i64 = conn
+                    .query_row("SELECT count FROM indicator_meta WHERE indicator = ?1", [&name], |row| row.get(0))
+                    .map_err(|e| e.to_string())?;
+                let mut series = vec![None; count.max(0) as usize];
+                let mut stmt = conn
+                    .prepare("SELECT idx, value FROM indicator_values WHERE indicator = ?1")
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map([&name], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<f64>>(1)?)))
+                    .map_err(|e| e.to_string())?;
+                for row in rows {
+                    let (idx, value) = row.map_err(|e| e.to_string())?;
+                    if let Some(slot) = series.get_mut(idx as usize) {
+                        *slot = value;
+                    }
+                }
+                conn.execute(
+                    "UPDATE indicator_meta SET data = ?1 WHERE indicator = ?2",
+                    (columnar::encode_values(&series)?, &name),
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    for table in ROW_TABLES {
+        conn.execute_batch(&format!("DROP TABLE IF EXISTS {};", table))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, String> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(count > 0)
+}
+
+fn distinct(conn: &Connection, sql: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn read_rows(conn: &Connection, sql: &str, target: Option<&str>) -> Result<Vec<Candle>, String> {
+    let mut stmt = conn
+        .prepare(&sql.replace("{}", columnar::ROW_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = match target {
+        Some(target) => stmt.query_map([target], columnar::candle_from_row),
+        None => stmt.query_map([], columnar::candle_from_row),
+    }
+    .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn add_missing_columns(conn: &Connection, table: &str, columns: &[(&str, &str)]) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|e| e.to_string())?;
+    let existing = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    // a missing table is created whole by SCHEMA afterwards
+    if existing.is_empty() {
+        return Ok(());
+    }
+    for (name, ty) in columns {
+        if !existing.iter().any(|c| c == name) {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, name, ty))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use super::super::columnar::decode_candles;
+    use super::super::schema::{open, user_version, SCHEMA_VERSION};
+    use std::path::PathBuf;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fxgui_{}_{}.sqlite", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn unstamped_cache_is_migrated_in_place() {
+        let path = temp_db("schema_migrate");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE dataset_meta (source_path TEXT);\n\
+                 CREATE TABLE candles (ts_utc TEXT, open REAL, high REAL, low REAL, close REAL, volume REAL);\n\
+                 INSERT INTO candles VALUES ('2015-03-18T14:00:00Z', 1.1, 1.3, 1.0, 1.2, 42);\n\
+                 CREATE TABLE indicator_meta (indicator TEXT PRIMARY KEY, count INTEGER);\n\
+                 CREATE TABLE indicator_values (indicator TEXT, idx INTEGER, value REAL);\n\
+                 INSERT INTO indicator_meta VALUES ('ma', 2);\n\
+                 INSERT INTO indicator_values VALUES ('ma', 1, 0.5);",
+            )
+            .unwrap();
+        }
+        let conn = open(&path).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), SCHEMA_VERSION);
+        let data: Vec<u8> = conn
+            .query_row("SELECT data FROM candle_blocks WHERE series = ''", [], |row| row.get(0))
+            .unwrap();
+        let candles = decode_candles(&data).unwrap();
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].close, 1.2);
+        assert_eq!(candles[0].spread, None);
+        // name-only indicator entries cannot say which parameters they used
+        let indicators: i64 = conn
+            .query_row("SELECT COUNT(*) FROM indicator_meta", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indicators, 0);
+        let legacy: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('candles', 'indicator_values')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(legacy, 0);
+        drop(conn);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn newer_cache_is_rebuilt_empty() {
+        let path = temp_db("schema_newer");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE candle_blocks (series TEXT, future_column BLOB);\n\
+                 INSERT INTO candle_blocks VALUES ('', x'00');",
+            )
+            .unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        }
+        let conn = open(&path).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), SCHEMA_VERSION);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM candle_blocks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        drop(conn);
+        let _ = std::fs::remove_file(path);
+    }
+}
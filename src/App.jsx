@@ -17,12 +17,22 @@ const perfLog = (label, start) => {
 };
 const perfStart = () => (import.meta.env.DEV ? performance.now() : 0);
 
+// descriptor sent to indicator_range / compute_indicators for a pane; the
+// backend fills in parameters left at their defaults
+const indicatorRequest = (pane) => ({
+  kind: pane.indicator.toLowerCase(),
+  params: pane.indicatorParams?.[pane.indicator] ?? {},
+  source: pane.indicatorSource ?? "close",
+});
+
 const emptyPane = (idx) => ({
   id: idx,
   pair: "USD/JPY",
   timeframe: "M1",
   chartType: "Candlestick",
   indicator: "MA",
+  indicatorParams: {},
+  indicatorSource: "close",
   indicatorData: null,
   rawDataset: null,
   candles: [],
@@ -52,9 +62,13 @@ function App() {
   const [datasetHistory, setDatasetHistory] = useState([]);
   const [playbackReady, setPlaybackReady] = useState(false);
   const [ingestProgress, setIngestProgress] = useState(null);
+  const [indicatorCatalog, setIndicatorCatalog] = useState([]);
 
   const panes = useMemo(() => paneState.slice(0, split), [paneState, split]);
   const active = paneState[activePane];
+  const activeIndicator = indicatorCatalog.find(
+    (info) => info.kind === active.indicator.toLowerCase()
+  );
 
   const updatePane = (idx, patch) => {
     setPaneState((prev) =>
@@ -62,25 +76,28 @@ function App() {
     );
   };
 
-  const updateRange = async (idx, nextOffset, nextBars) => {
-    const pane = paneState[idx];
+  const updateRange = async (idx, nextOffset, nextBars, panePatch = {}) => {
+    const pane = paneState[idx] && { ...paneState[idx], ...panePatch };
     if (!pane || !pane.rawDataset || !pane.rawDataset.source_path) {
       return;
     }
     const sourcePath = pane.rawDataset.source_path;
+    const timeframe = pane.rawDataset.timeframe ?? null;
     const totalBars = pane.rawDataset.candles.length;
     const bars = clamp(nextBars, 20, Math.max(20, totalBars));
     const maxOffset = Math.max(0, totalBars - bars);
     const offset = clamp(nextOffset, 0, maxOffset);
     try {
-      const indicator = pane.indicator;
+      const indicator = indicatorRequest(pane);
       const range = await invoke("dataset_range", {
         sourcePath,
+        timeframe,
         offset,
         limit: bars,
       });
       const indicators = await invoke("indicator_range", {
         sourcePath,
+        timeframe,
         offset,
         limit: bars,
         indicator,
@@ -192,6 +209,12 @@ function App() {
     updateRange(activePane, nextOffset, active.viewBars);
   }, [active.seek, activePane, maxBars, syncEnabled]);
 
+  useEffect(() => {
+    invoke("list_indicators")
+      .then(setIndicatorCatalog)
+      .catch((err) => setIngestError(String(err)));
+  }, []);
+
   const ingestRangeRef = useRef({ activePane: 0, viewBars: 240 });
 
   useEffect(() => {
@@ -454,33 +477,39 @@ function App() {
     }
   };
 
-  const applyTimeframe = async (nextTf) => {
+  const applyTimeframe = async (nextTf, heikinAshi = active.chartType === "Heikin-Ashi") => {
     updatePane(activePane, { timeframe: nextTf });
     const dataset = active.rawDataset || null;
     if (!dataset) return;
     try {
       const t0 = perfStart();
+      // Heikin-Ashi candles come from the backend so indicators use HA closes
       const resampled = await invoke("resample_dataset", {
         dataset,
-        target: nextTf,
+        target: heikinAshi ? `HA:${nextTf}` : nextTf,
       });
       perfLog("ipc.resample_dataset", t0);
       const t1 = perfStart();
-      await invoke("compute_indicators", { dataset: resampled });
+      await invoke("compute_indicators", {
+        dataset: resampled,
+        indicators: [indicatorRequest(active)],
+      });
       perfLog("ipc.compute_indicators", t1);
       const t2 = perfStart();
       const totalBars = resampled.candles.length;
       const nextBars = Math.min(240, totalBars || 240);
       const initRange = await invoke("dataset_range", {
         sourcePath: resampled.source_path,
+        timeframe: resampled.timeframe ?? null,
         offset: 0,
         limit: nextBars,
       });
       const initIndicators = await invoke("indicator_range", {
         sourcePath: resampled.source_path,
+        timeframe: resampled.timeframe ?? null,
         offset: 0,
         limit: nextBars,
-        indicator: active.indicator,
+        indicator: indicatorRequest(active),
       });
       updatePane(activePane, {
         rawDataset: resampled,
@@ -676,6 +705,9 @@ function App() {
                   totalBars={pane.rawDataset?.candles.length || pane.candles.length}
                   indicatorData={pane.indicatorData}
                   indicatorType={pane.indicator}
+                  indicatorPlacement={
+                    indicatorCatalog.find((info) => info.kind === pane.indicator.toLowerCase())?.placement
+                  }
                   chartType={pane.chartType}
                   onViewChange={(next) => {
                     if (next.viewBars !== undefined) {
@@ -739,7 +771,7 @@ function App() {
           <div className="setting-block">
             <label>足の種類</label>
             <div className="segmented">
-              {["M1", "M5", "M15", "M30", "H1", "H4", "D1"].map((tf) => (
+              {["M1", "M5", "M15", "M30", "H1", "H4", "D1", "W1", "MN1"].map((tf) => (
                 <button
                   key={tf}
                   type="button"
@@ -754,30 +786,69 @@ function App() {
           <div className="setting-block">
             <label>インジケーター</label>
             <div className="segmented">
-              {["MA", "RSI", "MACD"].map((ind) => (
-                <button
-                  key={ind}
-                  type="button"
-                  className={active.indicator === ind ? "active" : ""}
-                  onClick={() => {
-                    updatePane(activePane, { indicator: ind });
-                    updateRange(activePane, active.viewOffset, active.viewBars);
-                  }}
-                >
-                  {ind}
-                </button>
-              ))}
+              {indicatorCatalog.map((info) => {
+                const ind = info.kind.toUpperCase();
+                return (
+                  <button
+                    key={info.kind}
+                    type="button"
+                    title={info.name}
+                    className={active.indicator === ind ? "active" : ""}
+                    onClick={() => {
+                      updatePane(activePane, { indicator: ind });
+                      updateRange(activePane, active.viewOffset, active.viewBars, { indicator: ind });
+                    }}
+                  >
+                    {ind}
+                  </button>
+                );
+              })}
             </div>
+            {activeIndicator && activeIndicator.params.length > 0 ? (
+              <div className="indicator-params">
+                {activeIndicator.params.map((param) => (
+                  <label key={`${activePane}-${active.indicator}-${param.name}`}>
+                    {param.name}
+                    <input
+                      type="number"
+                      min={param.min}
+                      max={param.max}
+                      step={param.integer ? 1 : "any"}
+                      defaultValue={active.indicatorParams?.[active.indicator]?.[param.name] ?? param.default}
+                      onChange={(e) => {
+                        const value = Number(e.target.value);
+                        if (e.target.value === "" || value < param.min || value > param.max) return;
+                        if (param.integer && !Number.isInteger(value)) return;
+                        const indicatorParams = {
+                          ...active.indicatorParams,
+                          [active.indicator]: {
+                            ...active.indicatorParams?.[active.indicator],
+                            [param.name]: value,
+                          },
+                        };
+                        updatePane(activePane, { indicatorParams });
+                        updateRange(activePane, active.viewOffset, active.viewBars, { indicatorParams });
+                      }}
+                    />
+                  </label>
+                ))}
+              </div>
+            ) : null}
           </div>
           <div className="setting-block">
             <label>チャート種別</label>
             <div className="segmented">
-              {["Candlestick", "Line", "Bar"].map((mode) => (
+              {["Candlestick", "Heikin-Ashi", "Line", "Bar"].map((mode) => (
                 <button
                   key={mode}
                   type="button"
                   className={active.chartType === mode ? "active" : ""}
-                  onClick={() => updatePane(activePane, { chartType: mode })}
+                  onClick={() => {
+                    updatePane(activePane, { chartType: mode });
+                    if ((mode === "Heikin-Ashi") !== (active.chartType === "Heikin-Ashi")) {
+                      applyTimeframe(active.timeframe, mode === "Heikin-Ashi");
+                    }
+                  }}
                 >
                   {mode}
                 </button>
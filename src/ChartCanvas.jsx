@@ -64,6 +64,7 @@ export default function ChartCanvas({
   onViewChange,
   indicatorData,
   indicatorType,
+  indicatorPlacement,
   chartType,
   totalBars,
 }) {
@@ -174,10 +175,16 @@ export default function ChartCanvas({
           iMin = Math.min(iMin, value);
           iMax = Math.max(iMax, value);
         }
-        if (iMin !== Infinity && iMax !== -Infinity) {
+        if (indicatorPlacement === "overlay") {
+          // price-unit series share the candles' scale
+          iMin = min;
+          iMax = max;
+        } else if (iMin !== Infinity && iMax !== -Infinity) {
           const padding = (iMax - iMin) * 0.05 || 1;
           iMin -= padding;
           iMax += padding;
+        }
+        if (iMin !== Infinity && iMax !== -Infinity) {
           drawLineSeries(
             ctx,
             indicatorSlice,
@@ -201,7 +208,7 @@ export default function ChartCanvas({
       const duration = performance.now() - t0;
       console.info(`[perf] render.chart: ${duration.toFixed(1)}ms`);
     }
-  }, [candles, size, viewBars, viewOffset, crosshair, indicatorData, indicatorType, chartType]);
+  }, [candles, size, viewBars, viewOffset, crosshair, indicatorData, indicatorType, indicatorPlacement, chartType]);
 
   const handleWheel = (event) => {
     event.preventDefault();